@@ -0,0 +1,368 @@
+use crate::{Coord, Direction, Entity, EntityMarker, LayeredGrid, Loc, ParseableCharacters};
+use std::fmt::Debug;
+
+// What happens when an agent tries to move onto a tile another agent holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    // The mover stays where it is
+    Block,
+    // The two agents trade places
+    Swap,
+    // The mover is absorbed into the agent already there
+    Merge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickOrder {
+    // One at a time, in the order the agents were parsed
+    Listed,
+    // One at a time, top to bottom then left to right
+    ReadingOrder,
+    // Every agent decides from the same snapshot, then all move at once
+    Simultaneous,
+}
+
+// Where an agent wants to go this tick and the heading it wants to face
+// afterwards (None keeps its current heading). No intent means stay put.
+pub type Intent<C> = Option<(Loc<C>, Option<Direction>)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentPath<C = isize> {
+    path: Vec<Loc<C>>,
+    merged_into: Option<usize>,
+}
+impl<C: Coord> AgentPath<C> {
+    // Every Loc the agent has stood on, starting where it was parsed
+    pub fn path(&self) -> &[Loc<C>] {
+        &self.path
+    }
+    pub fn merged_into(&self) -> Option<usize> {
+        self.merged_into
+    }
+}
+
+// Moves every entity on a LayeredGrid once per tick. Agents are identified
+// by their index in the grid's entity list when the simulation starts.
+pub struct Simulation<T, E, C = isize> {
+    grid: LayeredGrid<T, E, C>,
+    collision: Collision,
+    order: TickOrder,
+    ids: Vec<usize>,
+    paths: Vec<AgentPath<C>>,
+    ticks: usize,
+}
+impl<T, E, C> Simulation<T, E, C>
+where
+    T: ParseableCharacters + Copy + TryFrom<char>,
+    <T as TryFrom<char>>::Error: Debug,
+    E: EntityMarker,
+    C: Coord,
+{
+    pub fn new(grid: LayeredGrid<T, E, C>, collision: Collision, order: TickOrder) -> Self {
+        let paths = grid
+            .entities()
+            .iter()
+            .map(|e| AgentPath {
+                path: vec![e.loc()],
+                merged_into: None,
+            })
+            .collect();
+        Self {
+            ids: (0..grid.entities().len()).collect(),
+            grid,
+            collision,
+            order,
+            paths,
+            ticks: 0,
+        }
+    }
+    pub fn grid(&self) -> &LayeredGrid<T, E, C> {
+        &self.grid
+    }
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+    pub fn paths(&self) -> &[AgentPath<C>] {
+        &self.paths
+    }
+    // The agent ids still on the grid, in entity order
+    pub fn active_agents(&self) -> &[usize] {
+        &self.ids
+    }
+
+    fn occupant(&self, loc: Loc<C>, mover: usize) -> Option<usize> {
+        (0..self.ids.len()).find(|i| *i != mover && self.grid.entities()[*i].loc() == loc)
+    }
+    fn move_agent(&mut self, index: usize, to: Loc<C>, heading: Option<Direction>) {
+        let entity = &mut self.grid.entities_mut()[index];
+        if let Some(heading) = heading {
+            entity.set_heading(Some(heading));
+        }
+        if entity.loc() != to {
+            entity.set_loc(to);
+            self.paths[self.ids[index]].path.push(to);
+        }
+    }
+    fn merge(&mut self, index: usize, into: usize) {
+        self.paths[self.ids[index]].merged_into = Some(self.ids[into]);
+        self.grid.remove_entity(index);
+        self.ids.remove(index);
+    }
+
+    pub fn tick(&mut self, plan: impl FnMut(&LayeredGrid<T, E, C>, &Entity<E, C>) -> Intent<C>) {
+        match self.order {
+            TickOrder::Simultaneous => self.tick_simultaneous(plan),
+            TickOrder::Listed | TickOrder::ReadingOrder => self.tick_sequential(plan),
+        }
+        self.ticks += 1;
+    }
+
+    pub fn run(
+        &mut self,
+        ticks: usize,
+        mut plan: impl FnMut(&LayeredGrid<T, E, C>, &Entity<E, C>) -> Intent<C>,
+    ) {
+        for _ in 0..ticks {
+            self.tick(&mut plan);
+        }
+    }
+
+    fn tick_sequential(
+        &mut self,
+        mut plan: impl FnMut(&LayeredGrid<T, E, C>, &Entity<E, C>) -> Intent<C>,
+    ) {
+        let mut order = self.ids.clone();
+        if self.order == TickOrder::ReadingOrder {
+            let entities = self.grid.entities();
+            order.sort_by_key(|id| {
+                let loc = entities[self.ids.iter().position(|i| i == id).unwrap()].loc();
+                (loc.get_y(), loc.get_x())
+            });
+        }
+        for id in order {
+            let Some(index) = self.ids.iter().position(|i| *i == id) else {
+                continue;
+            };
+            let entity = &self.grid.entities()[index];
+            let from = entity.loc();
+            let Some((to, heading)) = plan(&self.grid, entity) else {
+                continue;
+            };
+            match (self.occupant(to, index), self.collision) {
+                (None, _) => self.move_agent(index, to, heading),
+                (Some(_), Collision::Block) => {}
+                (Some(other), Collision::Swap) => {
+                    self.move_agent(other, from, None);
+                    self.move_agent(index, to, heading);
+                }
+                (Some(other), Collision::Merge) => {
+                    self.move_agent(index, to, heading);
+                    self.merge(index, other);
+                }
+            }
+        }
+    }
+
+    fn tick_simultaneous(
+        &mut self,
+        mut plan: impl FnMut(&LayeredGrid<T, E, C>, &Entity<E, C>) -> Intent<C>,
+    ) {
+        let current: Vec<Loc<C>> = self.grid.entities().iter().map(|e| e.loc()).collect();
+        let intents: Vec<Intent<C>> = self
+            .grid
+            .entities()
+            .iter()
+            .map(|e| plan(&self.grid, e))
+            .collect();
+        let target: Vec<Loc<C>> = intents
+            .iter()
+            .zip(&current)
+            .map(|(intent, loc)| intent.map_or(*loc, |(to, _)| to))
+            .collect();
+        let mut moving: Vec<bool> = intents.iter().map(|i| i.is_some()).collect();
+
+        // Cancel clashing moves until what's left can all happen together.
+        // Cancelling one move can create a new clash, hence the loop.
+        if self.collision != Collision::Merge {
+            loop {
+                let position = |j: usize| if moving[j] { target[j] } else { current[j] };
+                let clashes: Vec<usize> = (0..moving.len())
+                    .filter(|i| moving[*i] && target[*i] != current[*i])
+                    .filter(|i| {
+                        (0..moving.len()).any(|j| {
+                            let contested = moving[j] && position(j) == target[*i];
+                            let head_on =
+                                moving[j] && target[j] == current[*i] && target[*i] == current[j];
+                            let occupied = !moving[j] && position(j) == target[*i];
+                            j != *i
+                                && match self.collision {
+                                    Collision::Block => contested || head_on || occupied,
+                                    _ => contested,
+                                }
+                        })
+                    })
+                    .chain((0..moving.len()).filter(|i| {
+                        // A swap shoves the agent standing on the target back
+                        // onto this agent's square, so nobody else may be
+                        // stepping onto that square too.
+                        let shoves = (0..moving.len())
+                            .any(|j| !moving[j] && current[j] == target[*i] && j != *i);
+                        let followed = (0..moving.len())
+                            .any(|k| k != *i && moving[k] && target[k] == current[*i]);
+                        self.collision == Collision::Swap && moving[*i] && shoves && followed
+                    }))
+                    .collect();
+                if clashes.is_empty() {
+                    break;
+                }
+                for i in clashes {
+                    moving[i] = false;
+                }
+            }
+        }
+
+        for i in 0..moving.len() {
+            if let (true, Some((to, heading))) = (moving[i], intents[i]) {
+                self.move_agent(i, to, heading);
+            }
+        }
+        match self.collision {
+            Collision::Block => {}
+            Collision::Swap => {
+                for i in (0..moving.len()).filter(|i| moving[*i]) {
+                    if let Some(j) =
+                        (0..moving.len()).find(|j| !moving[*j] && current[*j] == target[i])
+                    {
+                        self.move_agent(j, current[i], None);
+                    }
+                }
+            }
+            Collision::Merge => {
+                for i in (0..target.len()).rev() {
+                    if let Some(into) = (0..i).find(|j| target[*j] == target[i]) {
+                        self.merge(i, into);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::Floor;
+    use itertools::Itertools;
+    use test_case::test_case;
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    struct Robot;
+    impl EntityMarker for Robot {
+        fn from_marker(marker: char) -> Option<(Self, Option<Direction>)> {
+            match marker {
+                '^' | '>' | 'v' | '<' => Some((Robot, Direction::try_from(marker).ok())),
+                _ => None,
+            }
+        }
+        fn to_marker(&self, _heading: Option<Direction>) -> char {
+            'R'
+        }
+        fn terrain_beneath(&self) -> char {
+            '.'
+        }
+    }
+
+    // Step forwards, or turn around when facing a wall
+    fn walk(grid: &LayeredGrid<Floor, Robot>, robot: &Entity<Robot>) -> Intent<isize> {
+        let heading = robot.heading()?;
+        let ahead = grid.terrain().get_nearby_tile(robot.loc(), heading, 1)?;
+        match ahead.get_type() {
+            Floor::Open => Some((*ahead.loc(), None)),
+            Floor::Wall => Some((robot.loc(), Some(heading.opposite()))),
+        }
+    }
+
+    fn simulation(collision: Collision, order: TickOrder) -> Simulation<Floor, Robot> {
+        let grid = LayeredGrid::from_puzzle_input("#######\n#>.<..#\n#######\n");
+        Simulation::new(grid, collision, order)
+    }
+
+    fn locs(simulation: &Simulation<Floor, Robot>) -> Vec<Loc> {
+        simulation
+            .grid()
+            .entities()
+            .iter()
+            .map(|e| e.loc())
+            .collect()
+    }
+
+    #[test_case(TickOrder::Simultaneous, vec![Loc::new(1, 1), Loc::new(3, 1)])]
+    #[test_case(TickOrder::Listed, vec![Loc::new(2, 1), Loc::new(3, 1)])]
+    fn test_block(order: TickOrder, exp: Vec<Loc>) {
+        let mut simulation = simulation(Collision::Block, order);
+        simulation.run(3, walk);
+        assert_eq!(locs(&simulation), exp);
+        assert_eq!(simulation.ticks(), 3);
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut simulation = simulation(Collision::Swap, TickOrder::Listed);
+        simulation.tick(walk);
+        assert_eq!(locs(&simulation), vec![Loc::new(3, 1), Loc::new(2, 1)]);
+        simulation.tick(walk);
+        assert_eq!(locs(&simulation), vec![Loc::new(4, 1), Loc::new(1, 1)]);
+        assert_eq!(
+            simulation.paths()[0].path(),
+            &[
+                Loc::new(1, 1),
+                Loc::new(2, 1),
+                Loc::new(3, 1),
+                Loc::new(4, 1)
+            ]
+        );
+        assert_eq!(
+            simulation.paths()[1].path(),
+            &[Loc::new(3, 1), Loc::new(2, 1), Loc::new(1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_swap_simultaneous_stationary() {
+        let grid = LayeredGrid::from_puzzle_input("#####\n#>^.#\n#####\n");
+        let mut simulation: Simulation<Floor, Robot> =
+            Simulation::new(grid, Collision::Swap, TickOrder::Simultaneous);
+        // Only the first robot moves; the second is shoved back into its place
+        simulation.tick(|grid, robot| match robot.heading()? {
+            Direction::East => walk(grid, robot),
+            _ => None,
+        });
+        assert_eq!(locs(&simulation), vec![Loc::new(2, 1), Loc::new(1, 1)]);
+    }
+
+    #[test]
+    fn test_swap_simultaneous_train() {
+        let grid = LayeredGrid::from_puzzle_input(">>^.\n");
+        let mut simulation: Simulation<Floor, Robot> =
+            Simulation::new(grid, Collision::Swap, TickOrder::Simultaneous);
+        // The middle robot can't shove the stationary one back onto the
+        // square the first robot is stepping into
+        simulation.tick(|grid, robot| match robot.heading()? {
+            Direction::East => walk(grid, robot),
+            _ => None,
+        });
+        let after = locs(&simulation);
+        assert_eq!(after, vec![Loc::new(1, 0), Loc::new(0, 0), Loc::new(2, 0)]);
+        assert_eq!(after.iter().unique().count(), 3);
+    }
+
+    #[test_case(TickOrder::Simultaneous)]
+    #[test_case(TickOrder::ReadingOrder)]
+    fn test_merge(order: TickOrder) {
+        let mut simulation = simulation(Collision::Merge, order);
+        simulation.tick(walk);
+        assert_eq!(simulation.active_agents(), &[0]);
+        assert_eq!(simulation.paths()[1].merged_into(), Some(0));
+        assert_eq!(locs(&simulation)[0], Loc::new(2, 1));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    Bounds, Collection, Coord, DirectedLocSet, Direction, Loc, LocSet, ParseableCharacters,
+};
+
+// How a tile treats a beam arriving with a given heading: the headings it
+// leaves with. Empty space passes it straight on, mirrors turn it, splitters
+// fan it out and absorbers return nothing.
+pub trait Redirects {
+    fn redirect(&self, heading: Direction) -> Vec<Direction>;
+}
+
+impl<T, C> Collection<T, C>
+where
+    T: ParseableCharacters + Copy + TryFrom<char> + Redirects,
+    C: Coord,
+{
+    // Every tile a beam passes through after entering `start` with `heading`.
+    // Each (Loc, Direction) state is only followed once, so loops terminate.
+    pub fn energise(&self, start: Loc<C>, heading: Direction) -> LocSet<C> {
+        let Some(bounds) = self.bounds() else {
+            return LocSet::new(Bounds::new(start, start));
+        };
+        let mut seen = DirectedLocSet::new(bounds);
+        let mut beams = vec![(start, heading)];
+        while let Some((loc, heading)) = beams.pop() {
+            let Some(tile) = self.get_tile(loc) else {
+                continue;
+            };
+            if !seen.insert(loc, heading) {
+                continue;
+            }
+            for out in tile.get_type().redirect(heading) {
+                if let Some(next) = self.get_nearby_tile(loc, out, C::one()) {
+                    beams.push((*next.loc(), out));
+                }
+            }
+        }
+        seen.locs()
+    }
+
+    // Every way into the grid from outside: each edge tile paired with the
+    // heading pointing inwards. Corner tiles appear once per edge.
+    pub fn edge_entries(&self) -> Vec<(Loc<C>, Direction)> {
+        Direction::get_cardinal()
+            .into_iter()
+            .flat_map(|heading| {
+                self.tiles()
+                    .iter()
+                    .filter(move |t| {
+                        self.get_nearby_tile(*t.loc(), heading.opposite(), C::one())
+                            .is_none()
+                    })
+                    .map(move |t| (*t.loc(), heading))
+            })
+            .collect()
+    }
+
+    pub fn most_energised(&self) -> Option<((Loc<C>, Direction), usize)> {
+        self.edge_entries()
+            .into_iter()
+            .map(|(loc, heading)| ((loc, heading), self.energise(loc, heading).len()))
+            .max_by_key(|(_, energised)| *energised)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BadTileTypeError;
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    enum Optic {
+        Empty,
+        ForwardMirror,
+        BackMirror,
+        VerticalSplitter,
+        HorizontalSplitter,
+        Absorber,
+    }
+    impl Redirects for Optic {
+        fn redirect(&self, heading: Direction) -> Vec<Direction> {
+            match (self, heading) {
+                (Optic::Empty, _) => vec![heading],
+                (Optic::Absorber, _) => vec![],
+                (Optic::ForwardMirror, Direction::East | Direction::West) => {
+                    vec![heading.rotate_counterclockwise()]
+                }
+                (Optic::ForwardMirror, _) => vec![heading.rotate_clockwise()],
+                (Optic::BackMirror, Direction::East | Direction::West) => {
+                    vec![heading.rotate_clockwise()]
+                }
+                (Optic::BackMirror, _) => vec![heading.rotate_counterclockwise()],
+                (Optic::VerticalSplitter, Direction::East | Direction::West) => {
+                    vec![Direction::North, Direction::South]
+                }
+                (Optic::HorizontalSplitter, Direction::North | Direction::South) => {
+                    vec![Direction::East, Direction::West]
+                }
+                (Optic::VerticalSplitter | Optic::HorizontalSplitter, _) => vec![heading],
+            }
+        }
+    }
+    impl TryFrom<char> for Optic {
+        type Error = BadTileTypeError;
+
+        fn try_from(value: char) -> Result<Self, Self::Error> {
+            match value {
+                '.' => Ok(Optic::Empty),
+                '/' => Ok(Optic::ForwardMirror),
+                '\\' => Ok(Optic::BackMirror),
+                '|' => Ok(Optic::VerticalSplitter),
+                '-' => Ok(Optic::HorizontalSplitter),
+                '#' => Ok(Optic::Absorber),
+                _ => Err(BadTileTypeError),
+            }
+        }
+    }
+    impl ParseableCharacters for Optic {
+        fn valid_chars() -> Vec<char> {
+            vec!['.', '/', '\\', '|', '-', '#']
+        }
+    }
+
+    #[test]
+    fn test_energise() {
+        let optics: Collection<Optic> =
+            Collection::from_puzzle_input(include_str!("./data/beam_sample.txt"));
+        let energised = optics.energise(Loc::new(0, 0), Direction::East);
+        assert_eq!(energised.len(), 46);
+        assert!(energised.contains(Loc::new(1, 9)));
+        assert!(!energised.contains(Loc::new(0, 9)));
+    }
+
+    #[test]
+    fn test_energise_absorber() {
+        let optics: Collection<Optic> = Collection::from_puzzle_input("..#.\n....\n");
+        assert_eq!(optics.energise(Loc::new(0, 0), Direction::East).len(), 3);
+    }
+
+    #[test]
+    fn test_edge_entries() {
+        let optics: Collection<Optic> =
+            Collection::from_puzzle_input(include_str!("./data/beam_sample.txt"));
+        assert_eq!(optics.edge_entries().len(), 40);
+    }
+
+    #[test]
+    fn test_most_energised() {
+        let optics: Collection<Optic> =
+            Collection::from_puzzle_input(include_str!("./data/beam_sample.txt"));
+        assert_eq!(
+            optics.most_energised(),
+            Some(((Loc::new(3, 0), Direction::South), 51))
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use std::{collections::HashMap, hash::Hash};
+
+// Applies `step` to `state` `n` times. Each state is fingerprinted with `key`,
+// and once a fingerprint repeats the remaining whole cycles are skipped, so `n`
+// can be in the billions as long as the states settle into a loop.
+pub fn repeat_with_cycle_detection<S, K>(
+    mut state: S,
+    n: usize,
+    key: impl Fn(&S) -> K,
+    mut step: impl FnMut(&mut S),
+) -> S
+where
+    S: Clone,
+    K: Hash + Eq,
+{
+    let mut history: Vec<S> = vec![];
+    let mut seen: HashMap<K, usize> = HashMap::new();
+    for i in 0..n {
+        if let Some(cycle_start) = seen.insert(key(&state), i) {
+            let cycle_len = i - cycle_start;
+            return history.swap_remove(cycle_start + (n - cycle_start) % cycle_len);
+        }
+        history.push(state.clone());
+        step(&mut state);
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0, 0)]
+    #[test_case(5, 14)]
+    #[test_case(8, 2)]
+    #[test_case(1_000_000_000, 9)]
+    #[test_case(1_000_000_001, 14)]
+    fn test_repeat_with_cycle_detection(n: usize, exp: u64) {
+        // 0 -> 1 -> (2 -> 5 -> 9 -> 14 -> 10 -> 16) -> 2 ...
+        let step = |x: &mut u64| *x = (*x * *x + 1) % 17;
+        let actual = repeat_with_cycle_detection(0u64, n, |x| *x, step);
+        assert_eq!(actual, exp);
+    }
+}
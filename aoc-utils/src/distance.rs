@@ -0,0 +1,237 @@
+use crate::{Collection, Coord, Loc, Stencil, Tile};
+use std::collections::VecDeque;
+
+// A pair of tiles where jumping straight from one to the other, ignoring
+// whatever lies between, beats the best route through the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shortcut<C = isize> {
+    from: Loc<C>,
+    to: Loc<C>,
+    length: u32,
+    saving: u32,
+}
+
+impl<C: Coord> Shortcut<C> {
+    pub fn from(&self) -> Loc<C> {
+        self.from
+    }
+    pub fn to(&self) -> Loc<C> {
+        self.to
+    }
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+    pub fn saving(&self) -> u32 {
+        self.saving
+    }
+}
+
+impl<T, C: Coord> Collection<T, C> {
+    // Steps from the nearest source to every tile, moving cardinally through
+    // passable tiles. Unreachable tiles are None.
+    pub fn distance_field(
+        &self,
+        sources: impl IntoIterator<Item = Loc<C>>,
+        passable: impl Fn(&Tile<T, C>) -> bool,
+    ) -> Collection<Option<u32>, C> {
+        self.distance_field_with(sources, &Stencil::cardinal(), passable)
+    }
+
+    pub fn distance_field_with(
+        &self,
+        sources: impl IntoIterator<Item = Loc<C>>,
+        stencil: &Stencil,
+        passable: impl Fn(&Tile<T, C>) -> bool,
+    ) -> Collection<Option<u32>, C> {
+        let mut distances: Vec<Option<u32>> = vec![None; self.len()];
+        let mut queue = VecDeque::new();
+        for source in sources {
+            if let Some(index) = self.position_of(source) {
+                distances[index] = Some(0);
+                queue.push_back((source, 0));
+            }
+        }
+        while let Some((loc, distance)) = queue.pop_front() {
+            for neighbour in self.get_neighbours(loc, stencil) {
+                let index = self.position_of(neighbour.loc).unwrap();
+                if distances[index].is_none() && passable(neighbour) {
+                    distances[index] = Some(distance + 1);
+                    queue.push_back((neighbour.loc, distance + 1));
+                }
+            }
+        }
+        Collection(
+            self.0
+                .iter()
+                .zip(distances)
+                .map(|(t, distance)| Tile::new(distance, t.loc))
+                .collect(),
+            self.1,
+        )
+    }
+}
+
+impl<C: Coord> Collection<Option<u32>, C> {
+    pub fn distance_at(&self, loc: Loc<C>) -> Option<u32> {
+        self.get_tile(loc).and_then(|t| *t.get_type())
+    }
+
+    // Adds two fields tile by tile. With one field measured from the start
+    // and one from the goal, each tile gets the length of the best route
+    // that passes through it.
+    pub fn through(&self, other: &Self) -> Self {
+        self.map(|t| Some(t.get_type().as_ref()? + other.distance_at(t.loc)?))
+    }
+
+    // Every jump of at most `radius` steps (Manhattan) between reachable
+    // tiles that shortens the best start to goal route by at least
+    // `min_saving`. `self` is measured from the start, `to_goal` from the goal.
+    pub fn shortcuts(&self, to_goal: &Self, radius: u32, min_saving: u32) -> Vec<Shortcut<C>> {
+        let Some(best) = self
+            .through(to_goal)
+            .0
+            .iter()
+            .filter_map(|t| t.tile_type)
+            .min()
+        else {
+            return vec![];
+        };
+        let jumps = Stencil::diamond(radius as isize);
+        let mut shortcuts = vec![];
+        for tile in &self.0 {
+            let Some(from_start) = tile.tile_type else {
+                continue;
+            };
+            for (dx, dy) in jumps.offsets() {
+                // Jumps that leave the coordinate type can't land anywhere
+                let Some(to) = num::cast(*dx)
+                    .zip(num::cast(*dy))
+                    .and_then(|(dx, dy)| tile.loc.get_offset(dx, dy))
+                else {
+                    continue;
+                };
+                let Some(from_goal) = to_goal.distance_at(to) else {
+                    continue;
+                };
+                let length = (dx.unsigned_abs() + dy.unsigned_abs()) as u32;
+                let saving = best.saturating_sub(from_start + length + from_goal);
+                if saving > 0 && saving >= min_saving {
+                    shortcuts.push(Shortcut {
+                        from: tile.loc,
+                        to,
+                        length,
+                        saving,
+                    });
+                }
+            }
+        }
+        shortcuts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::Maze;
+    use itertools::Itertools;
+    use test_case::test_case;
+
+    fn fields<C: Coord>(
+        track: &Collection<Maze, C>,
+    ) -> (Collection<Option<u32>, C>, Collection<Option<u32>, C>) {
+        let find = |tile_type| {
+            track
+                .tiles()
+                .iter()
+                .filter(|t| t.get_type() == &tile_type)
+                .map(|t| *t.loc())
+                .collect_vec()
+        };
+        let passable = |t: &Tile<Maze, C>| t.get_type() != &Maze::Wall;
+        (
+            track.distance_field(find(Maze::Start), passable),
+            track.distance_field(find(Maze::End), passable),
+        )
+    }
+
+    #[test]
+    fn test_distance_field() {
+        let track: Collection<Maze> = Collection::from_puzzle_input("S.#\n#..\n..E\n");
+        let (from_start, _) = fields(&track);
+        assert_eq!(
+            from_start.render_with(|t| t.get_type().map_or("-".to_string(), |d| d.to_string())),
+            "01-\n-23\n434\n"
+        );
+        assert_eq!(from_start.distance_at(Loc::new(2, 2)), Some(4));
+        assert_eq!(from_start.distance_at(Loc::new(2, 0)), None);
+        assert_eq!(from_start.distance_at(Loc::new(9, 9)), None);
+    }
+
+    #[test]
+    fn test_multiple_sources() {
+        let track: Collection<Maze> = Collection::from_puzzle_input("S...S\n");
+        let field = track.distance_field([Loc::new(0, 0), Loc::new(4, 0)], |_| true);
+        assert_eq!(
+            field
+                .tiles()
+                .iter()
+                .map(|t| t.get_type().unwrap())
+                .collect_vec(),
+            vec![0, 1, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_through() {
+        let track: Collection<Maze> = Collection::from_puzzle_input("S.#\n#..\n..E\n");
+        let (from_start, to_end) = fields(&track);
+        let through = from_start.through(&to_end);
+        assert_eq!(through.distance_at(Loc::new(1, 1)), Some(4));
+        assert_eq!(through.distance_at(Loc::new(0, 2)), Some(6));
+        assert_eq!(through.distance_at(Loc::new(0, 1)), None);
+    }
+
+    #[test_case(2, 1, 44)]
+    #[test_case(2, 20, 5)]
+    #[test_case(20, 50, 285)]
+    #[test_case(20, 76, 3)]
+    fn test_shortcuts(radius: u32, min_saving: u32, exp: usize) {
+        let track: Collection<Maze> =
+            Collection::from_puzzle_input(include_str!("./data/race_sample.txt"));
+        let (from_start, to_end) = fields(&track);
+        let shortcuts = from_start.shortcuts(&to_end, radius, min_saving);
+        assert_eq!(shortcuts.len(), exp);
+        assert!(shortcuts
+            .iter()
+            .all(|s| s.length() <= radius && s.saving() >= min_saving));
+    }
+
+    #[test]
+    fn test_shortcuts_at_coordinate_edge() {
+        // 128 columns, so jumps east from the start overflow an i8
+        let inp = format!("{}E#.S\n{}\n", ".".repeat(124), ".".repeat(128));
+        let described = |shortcuts: Vec<Shortcut<isize>>| {
+            shortcuts
+                .iter()
+                .map(|s| (s.from(), s.to(), s.length(), s.saving()))
+                .collect_vec()
+        };
+        let wide: Collection<Maze> = Collection::from_puzzle_input(&inp);
+        let (from_start, to_end) = fields(&wide);
+        let expected = described(from_start.shortcuts(&to_end, 2, 1));
+        let narrow: Collection<Maze, i8> = Collection::from_puzzle_input(&inp);
+        let (from_start, to_end) = fields(&narrow);
+        let actual = from_start
+            .shortcuts(&to_end, 2, 1)
+            .iter()
+            .map(|s| Shortcut {
+                from: s.from().convert(),
+                to: s.to().convert(),
+                length: s.length(),
+                saving: s.saving(),
+            })
+            .collect_vec();
+        assert!(expected.contains(&(Loc::new(126, 0), Loc::new(124, 0), 2, 2)));
+        assert_eq!(described(actual), expected);
+    }
+}
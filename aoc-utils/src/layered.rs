@@ -0,0 +1,240 @@
+use crate::{
+    parse_collection_with, Collection, Coord, CoordinateConvention, Direction, Loc,
+    ParseableCharacters,
+};
+use std::fmt::{Debug, Display};
+
+// Characters in the puzzle input that mark something standing on the terrain,
+// like a guard or a robot, rather than the terrain itself.
+pub trait EntityMarker: Sized {
+    fn from_marker(marker: char) -> Option<(Self, Option<Direction>)>;
+    fn to_marker(&self, heading: Option<Direction>) -> char;
+    // The terrain character hidden underneath the marker
+    fn terrain_beneath(&self) -> char;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entity<E, C = isize> {
+    kind: E,
+    loc: Loc<C>,
+    heading: Option<Direction>,
+}
+impl<E, C: Coord> Entity<E, C> {
+    pub fn new(kind: E, loc: Loc<C>, heading: Option<Direction>) -> Self {
+        Self { kind, loc, heading }
+    }
+    pub fn kind(&self) -> &E {
+        &self.kind
+    }
+    pub fn loc(&self) -> Loc<C> {
+        self.loc
+    }
+    pub fn heading(&self) -> Option<Direction> {
+        self.heading
+    }
+    pub fn set_loc(&mut self, loc: Loc<C>) {
+        self.loc = loc;
+    }
+    pub fn set_heading(&mut self, heading: Option<Direction>) {
+        self.heading = heading;
+    }
+}
+
+// Static terrain with the things moving over it kept separately, so the
+// terrain never has to pretend a guard's tile is floor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayeredGrid<T, E, C = isize> {
+    terrain: Collection<T, C>,
+    entities: Vec<Entity<E, C>>,
+}
+impl<T, E, C> LayeredGrid<T, E, C>
+where
+    T: ParseableCharacters + Copy + TryFrom<char>,
+    <T as TryFrom<char>>::Error: Debug,
+    E: EntityMarker,
+    C: Coord,
+{
+    pub fn new(terrain: Collection<T, C>, entities: Vec<Entity<E, C>>) -> Self {
+        Self { terrain, entities }
+    }
+
+    pub fn from_puzzle_input(puzzle_input: &str) -> Self {
+        Self::from_puzzle_input_with(puzzle_input, CoordinateConvention::default())
+    }
+
+    // Markers are swapped for the terrain beneath them before parsing. Only
+    // the grid the parser consumes counts, so arrows in a move list after a
+    // blank line don't become entities.
+    pub fn from_puzzle_input_with(puzzle_input: &str, convention: CoordinateConvention) -> Self {
+        let mut markers = vec![];
+        let mut terrain_input = String::with_capacity(puzzle_input.len() + 1);
+        let mut tile_index = 0;
+        for c in puzzle_input.chars() {
+            if c == '\n' {
+                terrain_input.push(c);
+                continue;
+            }
+            match E::from_marker(c) {
+                Some((kind, heading)) => {
+                    let beneath = kind.terrain_beneath();
+                    markers.push((terrain_input.len(), tile_index, kind, heading));
+                    terrain_input.push(beneath);
+                }
+                None => terrain_input.push(c),
+            }
+            tile_index += 1;
+        }
+        if !terrain_input.ends_with('\n') {
+            terrain_input.push('\n');
+        }
+        let (rest, terrain) = parse_collection_with(convention)(&terrain_input).unwrap();
+        let consumed = terrain_input.len() - rest.len();
+        let entities = markers
+            .into_iter()
+            .filter(|(offset, ..)| *offset < consumed)
+            .filter_map(|(_, i, kind, heading)| {
+                Some(Entity::new(kind, terrain.0.get(i)?.loc, heading))
+            })
+            .collect();
+        Self { terrain, entities }
+    }
+
+    pub fn terrain(&self) -> &Collection<T, C> {
+        &self.terrain
+    }
+    pub fn terrain_mut(&mut self) -> &mut Collection<T, C> {
+        &mut self.terrain
+    }
+    pub fn entities(&self) -> &[Entity<E, C>] {
+        &self.entities
+    }
+    pub fn entities_mut(&mut self) -> &mut [Entity<E, C>] {
+        &mut self.entities
+    }
+    pub fn entities_at(&self, loc: Loc<C>) -> impl Iterator<Item = &Entity<E, C>> {
+        self.entities.iter().filter(move |e| e.loc == loc)
+    }
+    pub fn add_entity(&mut self, entity: Entity<E, C>) {
+        self.entities.push(entity);
+    }
+    pub fn remove_entity(&mut self, index: usize) -> Entity<E, C> {
+        self.entities.remove(index)
+    }
+    pub fn remove_entities_at(&mut self, loc: Loc<C>) -> Vec<Entity<E, C>> {
+        let (removed, kept) = std::mem::take(&mut self.entities)
+            .into_iter()
+            .partition(|e| e.loc == loc);
+        self.entities = kept;
+        removed
+    }
+}
+
+// Draws the terrain with entity markers on top; where entities share a tile
+// the last one wins.
+impl<T, E, C> Display for LayeredGrid<T, E, C>
+where
+    T: Display,
+    E: EntityMarker,
+    C: Coord,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.terrain.render_with(|tile| {
+            match self.entities.iter().rfind(|e| e.loc == tile.loc) {
+                Some(entity) => entity.kind.to_marker(entity.heading).to_string(),
+                None => tile.to_string(),
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::Floor, YAxis};
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    enum Thing {
+        Robot,
+        Box,
+    }
+    impl EntityMarker for Thing {
+        fn from_marker(marker: char) -> Option<(Self, Option<Direction>)> {
+            match marker {
+                'O' => Some((Thing::Box, None)),
+                '@' => Some((Thing::Robot, None)),
+                _ => Some((Thing::Robot, Some(Direction::try_from(marker).ok()?))),
+            }
+        }
+        fn to_marker(&self, heading: Option<Direction>) -> char {
+            match (self, heading) {
+                (Thing::Box, _) => 'O',
+                (Thing::Robot, None) => '@',
+                (Thing::Robot, Some(Direction::North)) => '^',
+                (Thing::Robot, Some(Direction::East)) => '>',
+                (Thing::Robot, Some(Direction::South)) => 'v',
+                (Thing::Robot, Some(_)) => '<',
+            }
+        }
+        fn terrain_beneath(&self) -> char {
+            '.'
+        }
+    }
+
+    const GRID: &str = "#####\n#.O^#\n#@..#\n#####\n";
+
+    #[test]
+    fn test_parse_layered_grid() {
+        let grid: LayeredGrid<Floor, Thing> = LayeredGrid::from_puzzle_input(GRID);
+        assert_eq!(
+            grid.entities(),
+            &[
+                Entity::new(Thing::Box, Loc::new(2, 1), None),
+                Entity::new(Thing::Robot, Loc::new(3, 1), Some(Direction::North)),
+                Entity::new(Thing::Robot, Loc::new(1, 2), None),
+            ]
+        );
+        assert_eq!(grid.terrain().to_string(), "#####\n#...#\n#...#\n#####\n");
+        assert_eq!(grid.to_string(), GRID);
+    }
+
+    #[test]
+    fn test_markers_after_grid_are_ignored() {
+        let grid: LayeredGrid<Floor, Thing> =
+            LayeredGrid::from_puzzle_input(&format!("{}\n<^^>vO@\n", GRID));
+        assert_eq!(grid.entities().len(), 3);
+        assert_eq!(grid.to_string(), GRID);
+    }
+
+    #[test]
+    fn test_last_row_without_newline() {
+        let grid: LayeredGrid<Floor, Thing> = LayeredGrid::from_puzzle_input("#.#\n.@^");
+        assert_eq!(grid.terrain().len(), 6);
+        assert_eq!(
+            grid.entities(),
+            &[
+                Entity::new(Thing::Robot, Loc::new(1, 1), None),
+                Entity::new(Thing::Robot, Loc::new(2, 1), Some(Direction::North)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_move_entities() {
+        let mut grid: LayeredGrid<Floor, Thing> = LayeredGrid::from_puzzle_input(GRID);
+        let robot = &mut grid.entities_mut()[1];
+        robot.set_loc(Loc::new(3, 2));
+        robot.set_heading(Some(Direction::West));
+        assert_eq!(grid.entities_at(Loc::new(3, 2)).count(), 1);
+        let removed = grid.remove_entities_at(Loc::new(2, 1));
+        assert_eq!(removed.len(), 1);
+        assert_eq!(grid.to_string(), "#####\n#...#\n#@.<#\n#####\n");
+    }
+
+    #[test]
+    fn test_layered_grid_y_up() {
+        let convention = CoordinateConvention::default().with_y_axis(YAxis::Up);
+        let grid: LayeredGrid<Floor, Thing> = LayeredGrid::from_puzzle_input_with(GRID, convention);
+        assert_eq!(grid.entities()[2].loc(), Loc::new(1, 1));
+        assert_eq!(grid.to_string(), GRID);
+    }
+}
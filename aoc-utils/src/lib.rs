@@ -2,16 +2,47 @@ use itertools::Itertools;
 use nom::{
     character::complete::{newline, one_of},
     combinator::map,
+    error::ErrorKind,
     multi::{many0, many1},
     sequence::terminated,
     IResult,
 };
-use num::Integer;
-use std::fmt::{Debug, Display};
+use num::{Integer, PrimInt, Signed};
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+mod agents;
+mod beam;
+mod cycle;
+mod distance;
+mod layered;
+mod loc_set;
+mod path;
+mod pipes;
+mod push;
+mod reflection;
+mod search;
+mod tilt;
+pub use agents::{AgentPath, Collision, Intent, Simulation, TickOrder};
+pub use beam::Redirects;
+pub use cycle::repeat_with_cycle_detection;
+pub use distance::Shortcut;
+pub use layered::{Entity, EntityMarker, LayeredGrid};
+pub use loc_set::{DirectedLocSet, LocSet};
+pub use path::{BadMoveError, Path};
+pub use pipes::{Connects, PipeLoop};
+pub use push::{PushEngine, PushStep, Pushable};
+pub use reflection::{MirrorAxis, Reflection};
+pub use search::{search, OptimalPaths};
 
 #[derive(Debug)]
 pub struct BadTileTypeError;
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct BadDirectionError(pub char);
+
 pub fn parse_tile_type<T>(inp: &str) -> IResult<&str, T>
 where
     T: ParseableCharacters + TryFrom<char>,
@@ -29,17 +60,36 @@ where
     T: ParseableCharacters + TryFrom<char> + Copy,
     <T as TryFrom<char>>::Error: Debug,
 {
-    let (inp, rows) = many1(terminated(many1(parse_tile_type), newline))(inp)?;
-    let mut collection = Collection(vec![]);
-    for (row_num, row) in rows.iter().enumerate() {
-        for (col_num, tile) in row.iter().enumerate() {
-            collection.push(Tile::new(
-                *tile,
-                Loc::new(col_num as isize, row_num as isize),
-            ));
+    parse_collection_with(CoordinateConvention::default())(inp)
+}
+
+pub fn parse_collection_with<T, C>(
+    convention: CoordinateConvention,
+) -> impl Fn(&str) -> IResult<&str, Collection<T, C>>
+where
+    T: ParseableCharacters + TryFrom<char> + Copy,
+    <T as TryFrom<char>>::Error: Debug,
+    C: Coord,
+{
+    move |inp| {
+        let (rest, rows) = many1(terminated(many1(parse_tile_type), newline))(inp)?;
+        let too_large = || nom::Err::Failure(nom::error::Error::new(inp, ErrorKind::TooLarge));
+        let mut collection = Collection(vec![], convention);
+        for (row_num, row) in rows.iter().enumerate() {
+            let row_num = match convention.y_axis {
+                YAxis::Down => row_num,
+                YAxis::Up => rows.len() - 1 - row_num,
+            };
+            let y = num::cast::<isize, C>(row_num as isize + convention.origin)
+                .ok_or_else(too_large)?;
+            for (col_num, tile) in row.iter().enumerate() {
+                let x = num::cast::<isize, C>(col_num as isize + convention.origin)
+                    .ok_or_else(too_large)?;
+                collection.push(Tile::new(*tile, Loc::new(x, y)));
+            }
         }
+        Ok((rest, collection))
     }
-    Ok((inp, collection))
 }
 
 pub fn parse_collection_group<T>(inp: &str) -> IResult<&str, CollectionGroup<T>>
@@ -55,53 +105,75 @@ pub trait ParseableCharacters {
     fn valid_chars() -> Vec<char>;
 }
 
-#[derive(Debug, PartialEq, Copy, Clone, PartialOrd, Eq, Ord)]
-pub struct Loc {
-    x: isize,
-    y: isize,
+// The integer type a Loc is built from. isize is the default; i16 or i32 keep
+// visited sets and distance maps on big grids small.
+pub trait Coord:
+    PrimInt + Signed + Integer + Hash + Debug + Display + Default + Send + Sync + 'static
+{
+}
+impl<C> Coord for C where
+    C: PrimInt + Signed + Integer + Hash + Debug + Display + Default + Send + Sync + 'static
+{
 }
 
-impl Loc {
-    pub fn new(x: isize, y: isize) -> Self {
+#[derive(Debug, PartialEq, Copy, Clone, PartialOrd, Eq, Ord, Hash, Default)]
+pub struct Loc<C = isize> {
+    x: C,
+    y: C,
+}
+
+impl<C: Coord> Loc<C> {
+    pub fn new(x: C, y: C) -> Self {
         Self { x, y }
     }
-    pub fn get_nearby(&self, direction: Direction, distance: isize) -> Option<Self> {
-        Some(match direction {
-            Direction::North => Self::new(self.x, self.y.checked_sub(distance)?),
-            Direction::East => Self::new(self.x.checked_add(distance)?, self.y),
-            Direction::South => Self::new(self.x, self.y.checked_add(distance)?),
-            Direction::West => Self::new(self.x.checked_sub(distance)?, self.y),
-            Direction::NorthEast => {
-                Self::new(self.x.checked_add(distance)?, self.y.checked_sub(distance)?)
-            }
-            Direction::SouthEast => {
-                Self::new(self.x.checked_add(distance)?, self.y.checked_add(distance)?)
-            }
-            Direction::SouthWest => {
-                Self::new(self.x.checked_sub(distance)?, self.y.checked_add(distance)?)
-            }
-            Direction::NorthWest => {
-                Self::new(self.x.checked_sub(distance)?, self.y.checked_sub(distance)?)
-            }
-        })
+    pub fn get_nearby(&self, direction: Direction, distance: C) -> Option<Self> {
+        self.step(direction.delta(), distance)
+    }
+    pub fn get_nearby_with(
+        &self,
+        direction: Direction,
+        distance: C,
+        convention: &CoordinateConvention,
+    ) -> Option<Self> {
+        self.step(convention.delta(direction), distance)
+    }
+    fn step(&self, (dx, dy): (isize, isize), distance: C) -> Option<Self> {
+        let dx: C = num::cast(dx)?;
+        let dy: C = num::cast(dy)?;
+        self.get_offset(dx.checked_mul(&distance)?, dy.checked_mul(&distance)?)
     }
-    pub fn get_x(&self) -> isize {
+    pub fn get_offset(&self, dx: C, dy: C) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_add(&dx)?,
+            self.y.checked_add(&dy)?,
+        ))
+    }
+    pub fn get_x(&self) -> C {
         self.x
     }
-    pub fn get_y(&self) -> isize {
+    pub fn get_y(&self) -> C {
         self.y
     }
-    pub fn connect_with_line(&self, other: Loc) -> Vec<Loc> {
+    pub fn convert<D>(self) -> Loc<D>
+    where
+        D: Coord + From<C>,
+    {
+        Loc::new(self.x.into(), self.y.into())
+    }
+    pub fn try_convert<D: Coord>(self) -> Option<Loc<D>> {
+        Some(Loc::new(num::cast(self.x)?, num::cast(self.y)?))
+    }
+    pub fn connect_with_line(&self, other: Loc<C>) -> Vec<Loc<C>> {
         let mut y_slope = other.y - self.y;
         let mut x_slope = other.x - self.x;
         let divisor = x_slope.gcd(&y_slope);
-        x_slope /= divisor;
-        y_slope /= divisor;
+        x_slope = x_slope / divisor;
+        y_slope = y_slope / divisor;
         let mut traveling_point = *self;
         let mut line_points = vec![];
         while traveling_point != other {
-            traveling_point.x += x_slope;
-            traveling_point.y += y_slope;
+            traveling_point.x = traveling_point.x + x_slope;
+            traveling_point.y = traveling_point.y + y_slope;
             line_points.push(traveling_point);
         }
         line_points.pop();
@@ -109,10 +181,122 @@ impl Loc {
     }
 }
 
+impl<C: Display> Display for Loc<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+// Inclusive rectangle of Locs, used to lay dense per-tile storage over a grid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bounds<C = isize> {
+    min: Loc<C>,
+    max: Loc<C>,
+}
+impl<C: Coord> Bounds<C> {
+    pub fn new(min: Loc<C>, max: Loc<C>) -> Self {
+        Self { min, max }
+    }
+    pub fn min(&self) -> Loc<C> {
+        self.min
+    }
+    pub fn max(&self) -> Loc<C> {
+        self.max
+    }
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x).to_usize().map_or(0, |w| w + 1)
+    }
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y).to_usize().map_or(0, |h| h + 1)
+    }
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+    pub fn contains(&self, loc: Loc<C>) -> bool {
+        (self.min.x..=self.max.x).contains(&loc.x) && (self.min.y..=self.max.y).contains(&loc.y)
+    }
+    pub fn index_of(&self, loc: Loc<C>) -> Option<usize> {
+        if !self.contains(loc) {
+            return None;
+        }
+        let x = (loc.x - self.min.x).to_usize()?;
+        let y = (loc.y - self.min.y).to_usize()?;
+        Some(y * self.width() + x)
+    }
+    pub fn loc_at(&self, index: usize) -> Option<Loc<C>> {
+        if index >= self.area() {
+            return None;
+        }
+        let x: C = num::cast(index % self.width())?;
+        let y: C = num::cast(index / self.width())?;
+        Some(Loc::new(self.min.x + x, self.min.y + y))
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum YAxis {
+    #[default]
+    Down,
+    Up,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LocFormat {
+    #[default]
+    XY,
+    RowColumn,
+}
+
+// How a puzzle numbers its grid. The default matches screen coordinates:
+// (0, 0) top left, North is y - 1, printed as (x, y).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CoordinateConvention {
+    y_axis: YAxis,
+    origin: isize,
+    format: LocFormat,
+}
+impl CoordinateConvention {
+    pub fn new(y_axis: YAxis, origin: isize, format: LocFormat) -> Self {
+        Self {
+            y_axis,
+            origin,
+            format,
+        }
+    }
+    pub fn with_y_axis(self, y_axis: YAxis) -> Self {
+        Self { y_axis, ..self }
+    }
+    pub fn with_origin(self, origin: isize) -> Self {
+        Self { origin, ..self }
+    }
+    pub fn with_format(self, format: LocFormat) -> Self {
+        Self { format, ..self }
+    }
+    pub fn y_axis(&self) -> YAxis {
+        self.y_axis
+    }
+    pub fn origin(&self) -> isize {
+        self.origin
+    }
+    pub fn delta(&self, direction: Direction) -> (isize, isize) {
+        let (dx, dy) = direction.delta();
+        match self.y_axis {
+            YAxis::Down => (dx, dy),
+            YAxis::Up => (dx, -dy),
+        }
+    }
+    pub fn format_loc<C: Coord>(&self, loc: Loc<C>) -> String {
+        match self.format {
+            LocFormat::XY => loc.to_string(),
+            LocFormat::RowColumn => format!("({}, {})", loc.y, loc.x),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug)]
-pub struct Row<'a, T>(Vec<&'a Tile<T>>);
-impl<'a, T> IntoIterator for Row<'a, T> {
-    type Item = &'a Tile<T>;
+pub struct Row<'a, T, C = isize>(Vec<&'a Tile<T, C>>);
+impl<'a, T, C> IntoIterator for Row<'a, T, C> {
+    type Item = &'a Tile<T, C>;
 
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
@@ -120,8 +304,8 @@ impl<'a, T> IntoIterator for Row<'a, T> {
         self.0.into_iter()
     }
 }
-pub type Column<'a, T> = Row<'a, T>;
-impl<T> Display for Row<'_, T>
+pub type Column<'a, T, C = isize> = Row<'a, T, C>;
+impl<T, C> Display for Row<'_, T, C>
 where
     T: Display,
 {
@@ -131,18 +315,37 @@ where
     }
 }
 #[derive(Default, Debug, Clone, PartialEq)]
-pub struct Collection<T>(Vec<Tile<T>>);
-impl<T> Collection<T>
+pub struct Collection<T, C = isize>(Vec<Tile<T, C>>, CoordinateConvention);
+impl<T, C> Collection<T, C>
 where
     T: ParseableCharacters + Copy + TryFrom<char>,
+    C: Coord,
 {
-    fn push(&mut self, tile: Tile<T>) {
+    fn push(&mut self, tile: Tile<T, C>) {
         self.0.push(tile)
     }
-    pub fn get_row(&self, row_num: isize) -> Row<T> {
+    pub fn from_puzzle_input(puzzle_input: &str) -> Collection<T, C>
+    where
+        <T as TryFrom<char>>::Error: Debug,
+    {
+        Self::from_puzzle_input_with(puzzle_input, CoordinateConvention::default())
+    }
+
+    pub fn from_puzzle_input_with(
+        puzzle_input: &str,
+        convention: CoordinateConvention,
+    ) -> Collection<T, C>
+    where
+        <T as TryFrom<char>>::Error: Debug,
+    {
+        parse_collection_with(convention)(puzzle_input).unwrap().1
+    }
+}
+impl<T, C: Coord> Collection<T, C> {
+    pub fn get_row(&self, row_num: C) -> Row<'_, T, C> {
         Row(self.0.iter().filter(|t| t.loc.y == row_num).collect())
     }
-    pub fn get_column(&self, col_num: isize) -> Column<T> {
+    pub fn get_column(&self, col_num: C) -> Column<'_, T, C> {
         Row(self.0.iter().filter(|t| t.loc.x == col_num).collect())
     }
     pub fn count_rows(&self) -> usize {
@@ -157,28 +360,107 @@ where
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
-    pub fn tiles(&self) -> &Vec<Tile<T>> {
+    pub fn tiles(&self) -> &Vec<Tile<T, C>> {
         &self.0
     }
-    pub fn get_tile(&self, loc: Loc) -> Option<&Tile<T>> {
-        self.get_row(loc.y)
-            .0
-            .iter()
-            .find(|t| t.loc.x == loc.x)
-            .copied()
+    pub fn get_tile(&self, loc: Loc<C>) -> Option<&Tile<T, C>> {
+        self.position_of(loc).map(|index| &self.0[index])
     }
-
-    pub fn from_puzzle_input(puzzle_input: &str) -> Collection<T>
-    where
-        <T as TryFrom<char>>::Error: Debug,
-    {
-        parse_collection(puzzle_input).unwrap().1
+    pub fn get_tile_mut(&mut self, loc: Loc<C>) -> Option<&mut Tile<T, C>> {
+        self.position_of(loc).map(|index| &mut self.0[index])
+    }
+    fn position_of(&self, loc: Loc<C>) -> Option<usize> {
+        match self.row_major_index(loc) {
+            Some(Some(index)) if self.0[index].loc == loc => Some(index),
+            Some(None) => None,
+            _ => self.0.iter().position(|t| t.loc == loc),
+        }
+    }
+    // Parsed collections are full rectangles stored row by row, so a tile's
+    // position can be worked out from the first and last tiles. The outer None
+    // means the collection isn't laid out like that and has to be scanned.
+    fn row_major_index(&self, loc: Loc<C>) -> Option<Option<usize>> {
+        let first = self.0.first()?.loc;
+        let last = self.0.last()?.loc;
+        let width = (last.x - first.x).to_usize()? + 1;
+        let height = (last.y - first.y).abs().to_usize()? + 1;
+        if width * height != self.0.len() {
+            return None;
+        }
+        let x = (loc.x - first.x).to_usize().filter(|x| *x < width);
+        let y = match last.y >= first.y {
+            true => loc.y - first.y,
+            false => first.y - loc.y,
+        }
+        .to_usize()
+        .filter(|y| *y < height);
+        Some(x.zip(y).map(|(x, y)| y * width + x))
+    }
+    pub fn get_nearby_tile(
+        &self,
+        loc: Loc<C>,
+        direction: Direction,
+        distance: C,
+    ) -> Option<&Tile<T, C>> {
+        self.get_tile(loc.get_nearby_with(direction, distance, &self.1)?)
+    }
+    pub fn convention(&self) -> &CoordinateConvention {
+        &self.1
+    }
+    pub fn bounds(&self) -> Option<Bounds<C>> {
+        let (min_x, max_x) = self.0.iter().map(|t| t.loc.x).minmax().into_option()?;
+        let (min_y, max_y) = self.0.iter().map(|t| t.loc.y).minmax().into_option()?;
+        Some(Bounds::new(Loc::new(min_x, min_y), Loc::new(max_x, max_y)))
+    }
+    pub fn get_neighbours(&self, loc: Loc<C>, stencil: &Stencil) -> Vec<&Tile<T, C>> {
+        stencil
+            .apply(loc)
+            .filter_map(|neighbour| self.get_tile(neighbour))
+            .collect()
+    }
+    // A new grid of the same shape, with `f` choosing each tile's contents
+    pub fn map<U>(&self, f: impl Fn(&Tile<T, C>) -> U) -> Collection<U, C> {
+        Collection(
+            self.0.iter().map(|t| Tile::new(f(t), t.loc)).collect(),
+            self.1,
+        )
+    }
+    // Draws the grid row by row in reading order, with `draw` choosing what
+    // each tile looks like.
+    pub fn render_with(&self, draw: impl Fn(&Tile<T, C>) -> String) -> String {
+        let rows = self.0.iter().map(|t| t.loc.y).unique().sorted();
+        let rows: Vec<C> = match self.1.y_axis {
+            YAxis::Down => rows.collect(),
+            YAxis::Up => rows.rev().collect(),
+        };
+        let mut out = String::new();
+        for y in rows {
+            let row = self
+                .0
+                .iter()
+                .filter(|t| t.loc.y == y)
+                .sorted_by_key(|t| t.loc.x)
+                .map(&draw)
+                .join("");
+            out.push_str(&row);
+            out.push('\n');
+        }
+        out
+    }
+}
+impl<T, C> Display for Collection<T, C>
+where
+    T: Display,
+    C: Coord,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render_with(|t| t.to_string()))
     }
 }
 
-pub type CollectionGroup<T> = Vec<Collection<T>>;
+pub type CollectionGroup<T, C = isize> = Vec<Collection<T, C>>;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Direction {
     North,
     East,
@@ -190,6 +472,40 @@ pub enum Direction {
     NorthWest,
 }
 impl Direction {
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+            Direction::NorthEast => (1, -1),
+            Direction::SouthEast => (1, 1),
+            Direction::SouthWest => (-1, 1),
+            Direction::NorthWest => (-1, -1),
+        }
+    }
+    pub fn from_delta(delta: (isize, isize)) -> Option<Self> {
+        Self::get_all().into_iter().find(|d| d.delta() == delta)
+    }
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction::North => Self::South,
+            Direction::East => Self::West,
+            Direction::South => Self::North,
+            Direction::West => Self::East,
+            Direction::NorthEast => Self::SouthWest,
+            Direction::SouthEast => Self::NorthWest,
+            Direction::SouthWest => Self::NorthEast,
+            Direction::NorthWest => Self::SouthEast,
+        }
+    }
+    pub fn is_cardinal(&self) -> bool {
+        matches!(
+            self,
+            Direction::North | Direction::East | Direction::South | Direction::West
+        )
+    }
+
     pub fn rotate_clockwise(&self) -> Self {
         match self {
             Direction::North => Self::East,
@@ -215,6 +531,39 @@ impl Direction {
             Direction::NorthWest => Self::SouthWest,
         }
     }
+
+    pub fn rotate_clockwise_45(&self) -> Self {
+        match self {
+            Direction::North => Self::NorthEast,
+            Direction::NorthEast => Self::East,
+            Direction::East => Self::SouthEast,
+            Direction::SouthEast => Self::South,
+            Direction::South => Self::SouthWest,
+            Direction::SouthWest => Self::West,
+            Direction::West => Self::NorthWest,
+            Direction::NorthWest => Self::North,
+        }
+    }
+
+    pub fn rotate_counterclockwise_45(&self) -> Self {
+        match self {
+            Direction::North => Self::NorthWest,
+            Direction::NorthWest => Self::West,
+            Direction::West => Self::SouthWest,
+            Direction::SouthWest => Self::South,
+            Direction::South => Self::SouthEast,
+            Direction::SouthEast => Self::East,
+            Direction::East => Self::NorthEast,
+            Direction::NorthEast => Self::North,
+        }
+    }
+    pub fn turn(&self, turn: Turn) -> Self {
+        match turn {
+            Turn::Left => self.rotate_counterclockwise(),
+            Turn::Right => self.rotate_clockwise(),
+            Turn::Back => self.opposite(),
+        }
+    }
     pub fn get_cardinal() -> Vec<Self> {
         vec![
             Direction::North,
@@ -235,14 +584,106 @@ impl Direction {
             Direction::NorthWest,
         ]
     }
+    // Accepts arrow (^>v<), UDLR and NESW movement strings, ignoring whitespace
+    // so multi-line move lists parse in one go.
+    pub fn from_moves(inp: &str) -> Result<Vec<Self>, BadDirectionError> {
+        inp.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(Self::try_from)
+            .collect()
+    }
+}
+impl TryFrom<char> for Direction {
+    type Error = BadDirectionError;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '^' | 'U' | 'N' => Ok(Direction::North),
+            '>' | 'R' | 'E' => Ok(Direction::East),
+            'v' | 'D' | 'S' => Ok(Direction::South),
+            '<' | 'L' | 'W' => Ok(Direction::West),
+            _ => Err(BadDirectionError(value)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Turn {
+    Left,
+    Right,
+    Back,
+}
+
+// A set of relative offsets to visit from a Loc, for neighbourhoods that
+// aren't a single Direction times a distance (knight moves, diamonds, rings).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stencil(Vec<(isize, isize)>);
+impl Stencil {
+    pub fn new(offsets: Vec<(isize, isize)>) -> Self {
+        Self(offsets.into_iter().unique().collect())
+    }
+    pub fn from_directions(directions: &[Direction]) -> Self {
+        Self::new(directions.iter().map(|d| d.delta()).collect())
+    }
+    pub fn cardinal() -> Self {
+        Self::from_directions(&Direction::get_cardinal())
+    }
+    pub fn king() -> Self {
+        Self::from_directions(&Direction::get_all())
+    }
+    pub fn knight() -> Self {
+        Self::new(vec![
+            (1, -2),
+            (2, -1),
+            (2, 1),
+            (1, 2),
+            (-1, 2),
+            (-2, 1),
+            (-2, -1),
+            (-1, -2),
+        ])
+    }
+    pub fn diamond(radius: isize) -> Self {
+        Self::new(
+            (1..=radius)
+                .flat_map(|distance| Self::ring(distance).0)
+                .collect(),
+        )
+    }
+    pub fn ring(distance: isize) -> Self {
+        if distance == 0 {
+            return Self::new(vec![(0, 0)]);
+        }
+        let mut offsets = vec![];
+        for dx in -distance..=distance {
+            let dy = distance - dx.abs();
+            offsets.push((dx, -dy));
+            offsets.push((dx, dy));
+        }
+        Self::new(offsets)
+    }
+    pub fn offsets(&self) -> &[(isize, isize)] {
+        &self.0
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn apply<C: Coord>(&self, loc: Loc<C>) -> impl Iterator<Item = Loc<C>> + '_ {
+        self.0
+            .iter()
+            .filter_map(move |(dx, dy)| loc.get_offset(num::cast(*dx)?, num::cast(*dy)?))
+    }
 }
 
 #[derive(Debug, Copy, Clone, Ord, Eq, PartialOrd)]
-pub struct Tile<T> {
+pub struct Tile<T, C = isize> {
     tile_type: T,
-    loc: Loc,
+    loc: Loc<C>,
 }
-impl<T> Tile<T> {
+impl<T, C> Tile<T, C> {
     pub fn get_type(&self) -> &T {
         &self.tile_type
     }
@@ -252,11 +693,11 @@ impl<T> Tile<T> {
     pub fn set_type(&mut self, new_type: T) {
         self.tile_type = new_type;
     }
-    pub fn loc(&self) -> &Loc {
+    pub fn loc(&self) -> &Loc<C> {
         &self.loc
     }
 }
-impl<T> PartialEq for Tile<T>
+impl<T, C> PartialEq for Tile<T, C>
 where
     T: PartialEq,
 {
@@ -265,13 +706,13 @@ where
     }
 }
 
-impl<T> Tile<T> {
-    pub fn new(tile_type: T, loc: Loc) -> Self {
+impl<T, C> Tile<T, C> {
+    pub fn new(tile_type: T, loc: Loc<C>) -> Self {
         Self { tile_type, loc }
     }
 }
 
-impl<T> Display for Tile<T>
+impl<T, C> Display for Tile<T, C>
 where
     T: Display,
 {
@@ -286,7 +727,7 @@ mod tests {
     use test_case::test_case;
 
     #[derive(Debug, PartialEq, Copy, Clone)]
-    enum LavaTile {
+    pub(crate) enum LavaTile {
         Ash,
         Rocks,
     }
@@ -316,6 +757,64 @@ mod tests {
         }
     }
 
+    // Open floor and walls, for grids that things move around on
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    pub(crate) enum Floor {
+        Open,
+        Wall,
+    }
+    impl Display for Floor {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(match self {
+                Floor::Open => ".",
+                Floor::Wall => "#",
+            })
+        }
+    }
+    impl TryFrom<char> for Floor {
+        type Error = BadTileTypeError;
+
+        fn try_from(value: char) -> Result<Self, Self::Error> {
+            match value {
+                '.' => Ok(Floor::Open),
+                '#' => Ok(Floor::Wall),
+                _ => Err(BadTileTypeError),
+            }
+        }
+    }
+    impl ParseableCharacters for Floor {
+        fn valid_chars() -> Vec<char> {
+            vec!['.', '#']
+        }
+    }
+
+    // Walls and floor with a start and an end, for route finding
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    pub(crate) enum Maze {
+        Wall,
+        Floor,
+        Start,
+        End,
+    }
+    impl TryFrom<char> for Maze {
+        type Error = BadTileTypeError;
+
+        fn try_from(value: char) -> Result<Self, Self::Error> {
+            match value {
+                '#' => Ok(Maze::Wall),
+                '.' => Ok(Maze::Floor),
+                'S' => Ok(Maze::Start),
+                'E' => Ok(Maze::End),
+                _ => Err(BadTileTypeError),
+            }
+        }
+    }
+    impl ParseableCharacters for Maze {
+        fn valid_chars() -> Vec<char> {
+            vec!['#', '.', 'S', 'E']
+        }
+    }
+
     #[test_case(".", Ok(("", LavaTile::Ash)); "Ash")]
     #[test_case("#", Ok(("", LavaTile::Rocks)); "Rocks")]
     fn test_tile_type(inp: &str, exp: IResult<&str, LavaTile>) {
@@ -330,6 +829,18 @@ mod tests {
         assert_eq!(actual.0.len(), 63);
     }
 
+    #[test]
+    fn test_collection_bounds() {
+        let inp = include_str!("./data/lava_sample.txt");
+        let actual: Collection<LavaTile> = parse_collection(inp).unwrap().1;
+        let bounds = actual.bounds().unwrap();
+        assert_eq!(bounds, Bounds::new(Loc::new(0, 0), Loc::new(8, 6)));
+        assert_eq!(bounds.area(), actual.len());
+        assert_eq!(bounds.index_of(Loc::new(8, 1)), Some(17));
+        assert_eq!(bounds.loc_at(17), Some(Loc::new(8, 1)));
+        assert_eq!(bounds.index_of(Loc::new(9, 1)), None);
+    }
+
     #[test]
     fn test_parse_collection_group() {
         let inp = include_str!("./data/lava_sample.txt");
@@ -348,4 +859,175 @@ mod tests {
         let actual = point_1.connect_with_line(point_2);
         assert_eq!(actual, exp);
     }
+
+    #[test]
+    fn test_small_coordinate_types() {
+        assert_eq!(std::mem::size_of::<Loc<i16>>(), 4);
+        let loc: Loc<i16> = Loc::new(3, 4);
+        assert_eq!(
+            loc.get_nearby(Direction::NorthWest, 2),
+            Some(Loc::new(1, 2))
+        );
+        assert_eq!(Loc::new(i16::MAX, 0).get_nearby(Direction::East, 1), None);
+        assert_eq!(loc.convert::<i64>(), Loc::new(3i64, 4));
+        assert_eq!(Loc::new(70_000i32, 0).try_convert::<i16>(), None);
+        assert_eq!(
+            Loc::new(-7i64, 9).try_convert::<i16>(),
+            Some(Loc::new(-7, 9))
+        );
+
+        let inp = include_str!("./data/lava_sample.txt");
+        let small: Collection<LavaTile, i16> = Collection::from_puzzle_input(inp);
+        let default: Collection<LavaTile> = Collection::from_puzzle_input(inp);
+        assert_eq!(small.to_string(), default.to_string());
+        assert_eq!(
+            small.get_neighbours(Loc::new(4, 3), &Stencil::king()).len(),
+            8
+        );
+    }
+
+    #[test]
+    fn test_collection_display_round_trip() {
+        let inp = include_str!("./data/lava_sample.txt");
+        let collection: Collection<LavaTile> = parse_collection(inp).unwrap().1;
+        let first_grid = inp.split("\n\n").next().unwrap();
+        assert_eq!(collection.to_string(), format!("{}\n", first_grid));
+        let flipped: Collection<LavaTile> =
+            parse_collection_with(CoordinateConvention::default().with_y_axis(YAxis::Up))(inp)
+                .unwrap()
+                .1;
+        assert_eq!(flipped.to_string(), collection.to_string());
+    }
+
+    #[test]
+    fn test_coordinate_convention() {
+        let inp = "#..\n...\n..#\n";
+        let screen: Collection<LavaTile> = Collection::from_puzzle_input(inp);
+        assert_eq!(
+            screen.get_tile(Loc::new(0, 0)).unwrap().get_type(),
+            &LavaTile::Rocks
+        );
+        assert_eq!(
+            screen.get_tile(Loc::new(2, 2)).unwrap().get_type(),
+            &LavaTile::Rocks
+        );
+
+        let convention = CoordinateConvention::new(YAxis::Up, 1, LocFormat::RowColumn);
+        let y_up: Collection<LavaTile> = Collection::from_puzzle_input_with(inp, convention);
+        let top_left = Loc::new(1, 3);
+        assert_eq!(
+            y_up.get_tile(top_left).unwrap().get_type(),
+            &LavaTile::Rocks
+        );
+        assert_eq!(
+            y_up.get_tile(Loc::new(3, 1)).unwrap().get_type(),
+            &LavaTile::Rocks
+        );
+        assert_eq!(y_up.get_tile(Loc::new(0, 0)), None);
+        assert_eq!(
+            top_left.get_nearby_with(Direction::South, 1, &convention),
+            Some(Loc::new(1, 2))
+        );
+        assert_eq!(y_up.get_nearby_tile(top_left, Direction::North, 1), None);
+        assert_eq!(convention.format_loc(top_left), "(3, 1)");
+        assert_eq!(top_left.to_string(), "(1, 3)");
+    }
+
+    #[test_case(Direction::North, Direction::NorthEast)]
+    #[test_case(Direction::NorthEast, Direction::East)]
+    #[test_case(Direction::SouthWest, Direction::West)]
+    #[test_case(Direction::NorthWest, Direction::North)]
+    fn test_rotate_clockwise_45(inp: Direction, exp: Direction) {
+        assert_eq!(inp.rotate_clockwise_45(), exp);
+        assert_eq!(exp.rotate_counterclockwise_45(), inp);
+    }
+
+    #[test]
+    fn test_direction_delta_and_opposite() {
+        for direction in Direction::get_all() {
+            let (dx, dy) = direction.delta();
+            assert_eq!(direction.opposite().delta(), (-dx, -dy));
+            assert_eq!(direction.opposite().opposite(), direction);
+            assert_eq!(
+                direction.rotate_clockwise_45().rotate_clockwise_45(),
+                direction.rotate_clockwise()
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_tile_mut() {
+        let mut grid: Collection<LavaTile> = Collection::from_puzzle_input("#.\n..\n");
+        grid.get_tile_mut(Loc::new(1, 1))
+            .unwrap()
+            .set_type(LavaTile::Rocks);
+        assert!(grid.get_tile_mut(Loc::new(2, 1)).is_none());
+        assert_eq!(grid.to_string(), "#.\n.#\n");
+    }
+
+    #[test_case((0, -1), Some(Direction::North))]
+    #[test_case((-1, 1), Some(Direction::SouthWest))]
+    #[test_case((0, 2), None)]
+    #[test_case((0, 0), None)]
+    fn test_direction_from_delta(delta: (isize, isize), exp: Option<Direction>) {
+        assert_eq!(Direction::from_delta(delta), exp);
+    }
+
+    #[test_case(Direction::North, Turn::Left, Direction::West)]
+    #[test_case(Direction::North, Turn::Right, Direction::East)]
+    #[test_case(Direction::East, Turn::Back, Direction::West)]
+    #[test_case(Direction::SouthEast, Turn::Back, Direction::NorthWest)]
+    fn test_turn(inp: Direction, turn: Turn, exp: Direction) {
+        assert_eq!(inp.turn(turn), exp);
+    }
+
+    #[test_case("^>v<", Ok(vec![Direction::North, Direction::East, Direction::South, Direction::West]))]
+    #[test_case("UR\nDL", Ok(vec![Direction::North, Direction::East, Direction::South, Direction::West]))]
+    #[test_case("NESW", Ok(vec![Direction::North, Direction::East, Direction::South, Direction::West]))]
+    #[test_case("^x", Err(BadDirectionError('x')))]
+    fn test_direction_from_moves(inp: &str, exp: Result<Vec<Direction>, BadDirectionError>) {
+        assert_eq!(Direction::from_moves(inp), exp);
+    }
+
+    #[test_case(Stencil::cardinal(), 4)]
+    #[test_case(Stencil::king(), 8)]
+    #[test_case(Stencil::knight(), 8)]
+    #[test_case(Stencil::ring(0), 1)]
+    #[test_case(Stencil::ring(1), 4)]
+    #[test_case(Stencil::ring(2), 8)]
+    #[test_case(Stencil::ring(3), 12)]
+    #[test_case(Stencil::diamond(2), 12)]
+    #[test_case(Stencil::diamond(20), 840)]
+    fn test_stencil_len(stencil: Stencil, exp: usize) {
+        assert_eq!(stencil.len(), exp);
+    }
+
+    #[test]
+    fn test_get_neighbours() {
+        let inp = include_str!("./data/lava_sample.txt");
+        let collection: Collection<LavaTile> = parse_collection(inp).unwrap().1;
+        assert_eq!(
+            collection
+                .get_neighbours(Loc::new(0, 0), &Stencil::king())
+                .len(),
+            3
+        );
+        assert_eq!(
+            collection
+                .get_neighbours(Loc::new(4, 3), &Stencil::king())
+                .len(),
+            8
+        );
+        let knight_moves = collection.get_neighbours(Loc::new(0, 0), &Stencil::knight());
+        assert_eq!(
+            knight_moves.iter().map(|t| *t.loc()).collect::<Vec<Loc>>(),
+            vec![Loc::new(2, 1), Loc::new(1, 2)]
+        );
+        assert_eq!(
+            collection
+                .get_neighbours(Loc::new(4, 3), &Stencil::diamond(2))
+                .len(),
+            12
+        );
+    }
 }
@@ -0,0 +1,230 @@
+use crate::{Bounds, Coord, Direction, Loc};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BitSet(Vec<u64>);
+impl BitSet {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(WORD_BITS)])
+    }
+    fn insert(&mut self, index: usize) -> bool {
+        let (word, mask) = (index / WORD_BITS, 1 << (index % WORD_BITS));
+        let inserted = self.0[word] & mask == 0;
+        self.0[word] |= mask;
+        inserted
+    }
+    fn remove(&mut self, index: usize) -> bool {
+        let (word, mask) = (index / WORD_BITS, 1 << (index % WORD_BITS));
+        let removed = self.0[word] & mask != 0;
+        self.0[word] &= !mask;
+        removed
+    }
+    fn contains(&self, index: usize) -> bool {
+        self.0[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0
+    }
+    fn count(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+    fn combine(&self, other: &Self, op: impl Fn(u64, u64) -> u64) -> Self {
+        Self(
+            self.0
+                .iter()
+                .zip(&other.0)
+                .map(|(a, b)| op(*a, *b))
+                .collect(),
+        )
+    }
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, word)| {
+            (0..WORD_BITS)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| i * WORD_BITS + bit)
+        })
+    }
+}
+
+// A set of Locs stored as one bit per tile inside fixed bounds. Inserting a
+// Loc outside the bounds panics; asking whether it is contained does not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocSet<C = isize> {
+    bounds: Bounds<C>,
+    bits: BitSet,
+}
+impl<C: Coord> LocSet<C> {
+    pub fn new(bounds: Bounds<C>) -> Self {
+        Self {
+            bounds,
+            bits: BitSet::new(bounds.area()),
+        }
+    }
+    pub fn bounds(&self) -> &Bounds<C> {
+        &self.bounds
+    }
+    pub fn insert(&mut self, loc: Loc<C>) -> bool {
+        let index = self
+            .bounds
+            .index_of(loc)
+            .unwrap_or_else(|| panic!("{} is outside the LocSet bounds", loc));
+        self.bits.insert(index)
+    }
+    pub fn remove(&mut self, loc: Loc<C>) -> bool {
+        self.bounds
+            .index_of(loc)
+            .is_some_and(|index| self.bits.remove(index))
+    }
+    pub fn contains(&self, loc: Loc<C>) -> bool {
+        self.bounds
+            .index_of(loc)
+            .is_some_and(|index| self.bits.contains(index))
+    }
+    pub fn len(&self) -> usize {
+        self.bits.count()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn union(&self, other: &Self) -> Self {
+        assert_eq!(self.bounds, other.bounds, "LocSet bounds differ");
+        Self {
+            bounds: self.bounds,
+            bits: self.bits.combine(&other.bits, |a, b| a | b),
+        }
+    }
+    pub fn intersection(&self, other: &Self) -> Self {
+        assert_eq!(self.bounds, other.bounds, "LocSet bounds differ");
+        Self {
+            bounds: self.bounds,
+            bits: self.bits.combine(&other.bits, |a, b| a & b),
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item = Loc<C>> + '_ {
+        self.bits
+            .iter()
+            .filter_map(|index| self.bounds.loc_at(index))
+    }
+}
+
+// (Loc, Direction) states, for spotting when something walking the grid
+// comes back to a tile facing the same way it did before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectedLocSet<C = isize> {
+    bounds: Bounds<C>,
+    bits: BitSet,
+}
+impl<C: Coord> DirectedLocSet<C> {
+    const DIRECTIONS: usize = 8;
+
+    pub fn new(bounds: Bounds<C>) -> Self {
+        Self {
+            bounds,
+            bits: BitSet::new(bounds.area() * Self::DIRECTIONS),
+        }
+    }
+    fn index_of(&self, loc: Loc<C>, direction: Direction) -> Option<usize> {
+        let direction_index = Direction::get_all().iter().position(|d| *d == direction)?;
+        Some(self.bounds.index_of(loc)? * Self::DIRECTIONS + direction_index)
+    }
+    pub fn insert(&mut self, loc: Loc<C>, direction: Direction) -> bool {
+        let index = self
+            .index_of(loc, direction)
+            .unwrap_or_else(|| panic!("{} is outside the DirectedLocSet bounds", loc));
+        self.bits.insert(index)
+    }
+    pub fn contains(&self, loc: Loc<C>, direction: Direction) -> bool {
+        self.index_of(loc, direction)
+            .is_some_and(|index| self.bits.contains(index))
+    }
+    pub fn len(&self) -> usize {
+        self.bits.count()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Loc<C>, Direction)> + '_ {
+        let directions = Direction::get_all();
+        self.bits.iter().filter_map(move |index| {
+            Some((
+                self.bounds.loc_at(index / Self::DIRECTIONS)?,
+                directions[index % Self::DIRECTIONS],
+            ))
+        })
+    }
+    pub fn locs(&self) -> LocSet<C> {
+        let mut locs = LocSet::new(self.bounds);
+        for (loc, _) in self.iter() {
+            locs.insert(loc);
+        }
+        locs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds() -> Bounds {
+        Bounds::new(Loc::new(-2, -2), Loc::new(10, 70))
+    }
+
+    #[test]
+    fn test_loc_set() {
+        let mut set = LocSet::new(bounds());
+        assert!(set.is_empty());
+        assert!(set.insert(Loc::new(-2, -2)));
+        assert!(set.insert(Loc::new(10, 70)));
+        assert!(set.insert(Loc::new(3, 4)));
+        assert!(!set.insert(Loc::new(3, 4)));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(Loc::new(3, 4)));
+        assert!(!set.contains(Loc::new(4, 3)));
+        assert!(!set.contains(Loc::new(11, 0)));
+        assert!(set.remove(Loc::new(3, 4)));
+        assert!(!set.remove(Loc::new(3, 4)));
+        assert_eq!(
+            set.iter().collect::<Vec<Loc>>(),
+            vec![Loc::new(-2, -2), Loc::new(10, 70)]
+        );
+    }
+
+    #[test]
+    fn test_loc_set_union_and_intersection() {
+        let mut a = LocSet::new(bounds());
+        let mut b = LocSet::new(bounds());
+        a.insert(Loc::new(0, 0));
+        a.insert(Loc::new(1, 1));
+        b.insert(Loc::new(1, 1));
+        b.insert(Loc::new(2, 2));
+        assert_eq!(a.union(&b).len(), 3);
+        assert_eq!(
+            a.intersection(&b).iter().collect::<Vec<Loc>>(),
+            vec![Loc::new(1, 1)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_loc_set_insert_out_of_bounds() {
+        LocSet::new(bounds()).insert(Loc::new(11, 0));
+    }
+
+    #[test]
+    fn test_directed_loc_set() {
+        let mut set: DirectedLocSet<i16> =
+            DirectedLocSet::new(Bounds::new(Loc::new(0, 0), Loc::new(9, 9)));
+        assert!(set.insert(Loc::new(4, 6), Direction::North));
+        assert!(set.insert(Loc::new(4, 6), Direction::East));
+        assert!(!set.insert(Loc::new(4, 6), Direction::North));
+        assert!(set.contains(Loc::new(4, 6), Direction::East));
+        assert!(!set.contains(Loc::new(4, 6), Direction::South));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.locs().len(), 1);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![
+                (Loc::new(4, 6), Direction::North),
+                (Loc::new(4, 6), Direction::East)
+            ]
+        );
+    }
+}
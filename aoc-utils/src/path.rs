@@ -0,0 +1,316 @@
+use crate::{Collection, Coord, Direction, Loc};
+use itertools::Itertools;
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BadMoveError(pub String);
+
+// A walk across the grid as a list of waypoints. Consecutive waypoints must
+// share a row, column or diagonal; a plain Vec of adjacent Locs works, and so
+// does a compressed list of just the corners.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Path<C = isize>(Vec<Loc<C>>);
+
+impl<C: Coord> From<Vec<Loc<C>>> for Path<C> {
+    fn from(value: Vec<Loc<C>>) -> Self {
+        Self(value)
+    }
+}
+
+impl<C: Coord> Path<C> {
+    pub fn new(waypoints: Vec<Loc<C>>) -> Self {
+        Self(waypoints)
+    }
+
+    // Follows run-length moves like "R4 D2 L4" from `start`. Each move is a
+    // U/D/L/R or N/E/S/W letter then a distance; separators are optional.
+    pub fn from_moves(start: Loc<C>, moves: &str) -> Result<Self, BadMoveError> {
+        let mut waypoints = vec![start];
+        let mut chars = moves
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != ',')
+            .peekable();
+        while let Some(letter) = chars.next() {
+            let direction =
+                Direction::try_from(letter).map_err(|_| BadMoveError(letter.to_string()))?;
+            let digits: String = chars.peeking_take_while(|c| c.is_ascii_digit()).collect();
+            let distance: C = digits
+                .parse::<i64>()
+                .ok()
+                .and_then(num::cast)
+                .ok_or_else(|| BadMoveError(format!("{}{}", letter, digits)))?;
+            let last = *waypoints.last().unwrap();
+            let next = last
+                .get_nearby(direction, distance)
+                .ok_or_else(|| BadMoveError(format!("{}{}", letter, digits)))?;
+            waypoints.push(next);
+        }
+        Ok(Self(waypoints))
+    }
+
+    pub fn waypoints(&self) -> &[Loc<C>] {
+        &self.0
+    }
+    pub fn start(&self) -> Option<Loc<C>> {
+        self.0.first().copied()
+    }
+    pub fn end(&self) -> Option<Loc<C>> {
+        self.0.last().copied()
+    }
+    pub fn is_closed(&self) -> bool {
+        self.0.len() > 1 && self.start() == self.end()
+    }
+
+    fn segment(from: Loc<C>, to: Loc<C>) -> (Option<Direction>, usize) {
+        let dx = (to.get_x() - from.get_x()).to_isize().unwrap();
+        let dy = (to.get_y() - from.get_y()).to_isize().unwrap();
+        let length = dx.unsigned_abs().max(dy.unsigned_abs());
+        let straight = dx == 0 || dy == 0 || dx.abs() == dy.abs();
+        let direction = straight
+            .then(|| Direction::from_delta((dx.signum(), dy.signum())))
+            .flatten();
+        (direction, length)
+    }
+    fn segments(&self) -> impl Iterator<Item = (Option<Direction>, usize)> + '_ {
+        self.0
+            .iter()
+            .tuple_windows()
+            .map(|(a, b)| Self::segment(*a, *b))
+            .filter(|(_, length)| *length > 0)
+    }
+
+    // Number of single tile moves along the path
+    pub fn steps(&self) -> usize {
+        self.segments().map(|(_, length)| length).sum()
+    }
+    pub fn turns(&self) -> usize {
+        self.segments()
+            .map(|(direction, _)| direction)
+            .dedup()
+            .count()
+            .saturating_sub(1)
+    }
+
+    // Drops waypoints in the middle of straight runs
+    pub fn compress(&self) -> Self {
+        let mut waypoints: Vec<Loc<C>> = vec![];
+        for loc in &self.0 {
+            match waypoints.as_slice() {
+                [.., last] if last == loc => {}
+                [.., a, b] if Self::segment(*a, *b).0 == Self::segment(*b, *loc).0 => {
+                    *waypoints.last_mut().unwrap() = *loc;
+                }
+                _ => waypoints.push(*loc),
+            }
+        }
+        Self(waypoints)
+    }
+
+    // Every tile along the path, one per step
+    pub fn expand(&self) -> Vec<Loc<C>> {
+        let mut locs: Vec<Loc<C>> = self.0.iter().take(1).copied().collect();
+        for (from, to) in self.0.iter().tuple_windows() {
+            if let (Some(direction), length) = Self::segment(*from, *to) {
+                let mut loc = *from;
+                for _ in 0..length {
+                    loc = loc.get_nearby(direction, C::one()).unwrap();
+                    locs.push(loc);
+                }
+            }
+        }
+        locs
+    }
+
+    // Tiles the path passes through more than once. Ending a closed path back
+    // on the start doesn't count.
+    pub fn crossings(&self) -> Vec<Loc<C>> {
+        let mut locs = self.expand();
+        if self.is_closed() {
+            locs.pop();
+        }
+        let mut seen = HashSet::new();
+        locs.into_iter()
+            .filter(|loc| !seen.insert(*loc))
+            .unique()
+            .collect()
+    }
+    pub fn is_self_intersecting(&self) -> bool {
+        !self.crossings().is_empty()
+    }
+
+    // Run-length moves like "R4 D2", or None if a leg is diagonal
+    pub fn to_moves(&self) -> Option<String> {
+        self.compress()
+            .segments()
+            .map(|(direction, length)| {
+                let letter = match direction? {
+                    Direction::North => 'U',
+                    Direction::East => 'R',
+                    Direction::South => 'D',
+                    Direction::West => 'L',
+                    _ => return None,
+                };
+                Some(format!("{}{}", letter, length))
+            })
+            .collect::<Option<Vec<String>>>()
+            .map(|moves| moves.join(" "))
+    }
+
+    // Twice the area of the polygon through the waypoints, treating the path
+    // as closed whether or not it ends on its start.
+    fn double_area(&self) -> u64 {
+        let corners: Vec<(i64, i64)> = self
+            .0
+            .iter()
+            .map(|l| (num::cast(l.get_x()).unwrap(), num::cast(l.get_y()).unwrap()))
+            .collect();
+        let sum: i64 = corners
+            .iter()
+            .circular_tuple_windows()
+            .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+            .sum();
+        sum.unsigned_abs()
+    }
+    // Shoelace area of the polygon through the tile centres
+    pub fn area(&self) -> f64 {
+        self.double_area() as f64 / 2.0
+    }
+    fn boundary(&self) -> u64 {
+        let closing = match self.is_closed() {
+            true => 0,
+            false => match (self.end(), self.start()) {
+                (Some(end), Some(start)) => Self::segment(end, start).1,
+                _ => 0,
+            },
+        };
+        (self.steps() + closing) as u64
+    }
+    // Whole tiles strictly inside a closed loop, by Pick's theorem
+    pub fn count_enclosed(&self) -> u64 {
+        (self.double_area() + 2).saturating_sub(self.boundary()) / 2
+    }
+    // Tiles inside the loop plus the tiles the loop itself runs along
+    pub fn count_covered(&self) -> u64 {
+        self.count_enclosed() + self.boundary()
+    }
+
+    // Draws the grid with the path on top: an arrow on each tile showing
+    // which way the path leaves it.
+    pub fn render_on<T: std::fmt::Display>(&self, collection: &Collection<T, C>) -> String {
+        let locs = self.expand();
+        let mut arrows: Vec<(Loc<C>, char)> = locs
+            .iter()
+            .tuple_windows()
+            .map(|(from, to)| (*from, arrow(Self::segment(*from, *to).0)))
+            .collect();
+        if let Some(end) = locs.last() {
+            if !self.is_closed() {
+                arrows.push((*end, 'E'));
+            }
+        }
+        collection.render_with(
+            |tile| match arrows.iter().rfind(|(loc, _)| loc == tile.loc()) {
+                Some((_, arrow)) => arrow.to_string(),
+                None => tile.to_string(),
+            },
+        )
+    }
+}
+
+fn arrow(direction: Option<Direction>) -> char {
+    match direction {
+        Some(Direction::North) => '^',
+        Some(Direction::East) => '>',
+        Some(Direction::South) => 'v',
+        Some(Direction::West) => '<',
+        Some(Direction::NorthEast | Direction::SouthWest) => '/',
+        Some(Direction::NorthWest | Direction::SouthEast) => '\\',
+        None => '*',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::LavaTile;
+    use test_case::test_case;
+
+    fn square() -> Path {
+        Path::from_moves(Loc::new(0, 0), "R4 D2 L4 U2").unwrap()
+    }
+
+    #[test]
+    fn test_from_moves() {
+        assert_eq!(
+            square().waypoints(),
+            &[
+                Loc::new(0, 0),
+                Loc::new(4, 0),
+                Loc::new(4, 2),
+                Loc::new(0, 2),
+                Loc::new(0, 0)
+            ]
+        );
+        assert_eq!(
+            Path::<isize>::from_moves(Loc::new(0, 0), "R4 X2"),
+            Err(BadMoveError("X".to_string()))
+        );
+        assert_eq!(
+            Path::<isize>::from_moves(Loc::new(0, 0), "R4 D"),
+            Err(BadMoveError("D".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_path_measurements() {
+        let path = square();
+        assert!(path.is_closed());
+        assert_eq!(path.steps(), 12);
+        assert_eq!(path.turns(), 3);
+        assert_eq!(path.expand().len(), 13);
+        assert_eq!(path.area(), 8.0);
+        assert_eq!(path.count_enclosed(), 3);
+        assert_eq!(path.count_covered(), 15);
+        assert!(!path.is_self_intersecting());
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let expanded = Path::new(square().expand());
+        assert_eq!(expanded.waypoints().len(), 13);
+        assert_eq!(expanded.turns(), 3);
+        assert_eq!(expanded.compress(), square());
+        assert_eq!(expanded.to_moves(), Some("R4 D2 L4 U2".to_string()));
+        let diagonal = Path::new(vec![Loc::new(0, 0), Loc::new(2, 2)]);
+        assert_eq!(diagonal.steps(), 2);
+        assert_eq!(diagonal.to_moves(), None);
+    }
+
+    #[test_case("R3 D2 L1 U4", vec![Loc::new(2, 0)])]
+    #[test_case("R3 D2 L1 U1", vec![])]
+    #[test_case("R2 L1", vec![Loc::new(1, 0)])]
+    fn test_crossings(moves: &str, exp: Vec<Loc>) {
+        let path = Path::from_moves(Loc::new(0, 0), moves).unwrap();
+        assert_eq!(path.crossings(), exp);
+    }
+
+    #[test]
+    fn test_lagoon() {
+        let moves = "R6 D5 L2 D2 R2 D2 L5 U2 L1 U2 R2 U3 L2 U2";
+        let lagoon = Path::from_moves(Loc::new(0, 0), moves).unwrap();
+        assert_eq!(lagoon.count_covered(), 62);
+        let huge = Path::<i64>::from_moves(
+            Loc::new(0, 0),
+            "R461937 D56407 R356671 D863240 R367720 D266681 L577262 U829975 L112010 D829975 L491645 U686074 L5411 U500254",
+        )
+        .unwrap();
+        assert_eq!(huge.count_covered(), 952408144115);
+    }
+
+    #[test]
+    fn test_render_on() {
+        let grid: Collection<LavaTile> = Collection::from_puzzle_input("#####\n.....\n#####\n");
+        let path = Path::from_moves(Loc::new(0, 1), "R2 U1").unwrap();
+        assert_eq!(path.render_on(&grid), "##E##\n>>^..\n#####\n");
+    }
+}
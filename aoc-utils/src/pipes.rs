@@ -0,0 +1,231 @@
+use crate::{Collection, Coord, Direction, Loc, LocSet, ParseableCharacters, Path};
+
+// Tiles that link to their neighbours in some directions, like pipes, wires
+// or tracks. Two tiles are joined when each connects towards the other.
+pub trait Connects {
+    fn connections(&self) -> Vec<Direction>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipeLoop<C = isize> {
+    path: Vec<Loc<C>>,
+    start_connections: (Direction, Direction),
+}
+impl<C: Coord> PipeLoop<C> {
+    // Every tile on the loop once, starting with the start tile
+    pub fn path(&self) -> &[Loc<C>] {
+        &self.path
+    }
+    pub fn len(&self) -> usize {
+        self.path.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.path.is_empty()
+    }
+    // The shape the start tile must have for the loop to close
+    pub fn start_connections(&self) -> (Direction, Direction) {
+        self.start_connections
+    }
+    pub fn farthest_distance(&self) -> usize {
+        self.len() / 2
+    }
+    // Shoelace formula for the area, then Pick's theorem for the number of
+    // whole tiles strictly inside the loop.
+    pub fn count_enclosed(&self) -> usize {
+        Path::new(self.path.clone()).count_enclosed() as usize
+    }
+}
+
+impl<T, C> Collection<T, C>
+where
+    T: ParseableCharacters + Copy + TryFrom<char> + Connects,
+    C: Coord,
+{
+    fn connects_towards(&self, loc: Loc<C>, direction: Direction, start: Loc<C>) -> Option<Loc<C>> {
+        let neighbour = self.get_nearby_tile(loc, direction, C::one())?;
+        (*neighbour.loc() == start
+            || neighbour
+                .get_type()
+                .connections()
+                .contains(&direction.opposite()))
+        .then_some(*neighbour.loc())
+    }
+
+    // Follows the pipes out of `start` and back again. The start tile's own
+    // shape is ignored and inferred from whichever neighbours close the loop.
+    pub fn trace_loop(&self, start: Loc<C>) -> Option<PipeLoop<C>> {
+        self.get_tile(start)?;
+        for first in Direction::get_cardinal() {
+            let Some(mut loc) = self.connects_towards(start, first, start) else {
+                continue;
+            };
+            let mut heading = first;
+            let mut path = vec![start];
+            while loc != start && path.len() <= self.len() {
+                path.push(loc);
+                let connections = self.get_tile(loc)?.get_type().connections();
+                let Some((next, next_loc)) = connections
+                    .into_iter()
+                    .filter(|d| *d != heading.opposite())
+                    .find_map(|d| Some((d, self.connects_towards(loc, d, start)?)))
+                else {
+                    break;
+                };
+                heading = next;
+                loc = next_loc;
+            }
+            if loc == start {
+                return Some(PipeLoop {
+                    path,
+                    start_connections: (first, heading.opposite()),
+                });
+            }
+        }
+        None
+    }
+
+    // Counts tiles inside the loop by scanning each row and flipping between
+    // outside and inside whenever a loop tile connects North.
+    pub fn count_enclosed_by_parity(&self, pipe_loop: &PipeLoop<C>) -> usize {
+        let Some(bounds) = self.bounds() else {
+            return 0;
+        };
+        let mut on_loop = LocSet::new(bounds);
+        for loc in pipe_loop.path() {
+            on_loop.insert(*loc);
+        }
+        let start = pipe_loop.path()[0];
+        let connects_north = |loc: Loc<C>| match loc == start {
+            true => {
+                let (a, b) = pipe_loop.start_connections();
+                a == Direction::North || b == Direction::North
+            }
+            false => self
+                .get_tile(loc)
+                .is_some_and(|t| t.get_type().connections().contains(&Direction::North)),
+        };
+        let mut enclosed = 0;
+        for row in 0..bounds.height() {
+            let mut inside = false;
+            for column in 0..bounds.width() {
+                let loc = bounds.loc_at(row * bounds.width() + column).unwrap();
+                if on_loop.contains(loc) {
+                    if connects_north(loc) {
+                        inside = !inside;
+                    }
+                } else if inside && self.get_tile(loc).is_some() {
+                    enclosed += 1;
+                }
+            }
+        }
+        enclosed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BadTileTypeError, CoordinateConvention, YAxis};
+    use test_case::test_case;
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    enum Pipe {
+        Vertical,
+        Horizontal,
+        NorthEast,
+        NorthWest,
+        SouthWest,
+        SouthEast,
+        Ground,
+        Start,
+    }
+    impl Connects for Pipe {
+        fn connections(&self) -> Vec<Direction> {
+            match self {
+                Pipe::Vertical => vec![Direction::North, Direction::South],
+                Pipe::Horizontal => vec![Direction::East, Direction::West],
+                Pipe::NorthEast => vec![Direction::North, Direction::East],
+                Pipe::NorthWest => vec![Direction::North, Direction::West],
+                Pipe::SouthWest => vec![Direction::South, Direction::West],
+                Pipe::SouthEast => vec![Direction::South, Direction::East],
+                Pipe::Ground | Pipe::Start => vec![],
+            }
+        }
+    }
+    impl TryFrom<char> for Pipe {
+        type Error = BadTileTypeError;
+
+        fn try_from(value: char) -> Result<Self, Self::Error> {
+            match value {
+                '|' => Ok(Pipe::Vertical),
+                '-' => Ok(Pipe::Horizontal),
+                'L' => Ok(Pipe::NorthEast),
+                'J' => Ok(Pipe::NorthWest),
+                '7' => Ok(Pipe::SouthWest),
+                'F' => Ok(Pipe::SouthEast),
+                '.' => Ok(Pipe::Ground),
+                'S' => Ok(Pipe::Start),
+                _ => Err(BadTileTypeError),
+            }
+        }
+    }
+    impl ParseableCharacters for Pipe {
+        fn valid_chars() -> Vec<char> {
+            vec!['|', '-', 'L', 'J', '7', 'F', '.', 'S']
+        }
+    }
+
+    fn find_start<C: Coord>(pipes: &Collection<Pipe, C>) -> Loc<C> {
+        *pipes
+            .tiles()
+            .iter()
+            .find(|t| t.get_type() == &Pipe::Start)
+            .unwrap()
+            .loc()
+    }
+
+    #[test]
+    fn test_trace_loop() {
+        let pipes: Collection<Pipe> =
+            Collection::from_puzzle_input(include_str!("./data/pipes_sample.txt"));
+        let pipe_loop = pipes.trace_loop(find_start(&pipes)).unwrap();
+        assert_eq!(pipe_loop.len(), 16);
+        assert_eq!(pipe_loop.farthest_distance(), 8);
+        assert_eq!(
+            pipe_loop.start_connections(),
+            (Direction::East, Direction::South)
+        );
+        assert_eq!(
+            &pipe_loop.path()[..3],
+            &[Loc::new(0, 2), Loc::new(1, 2), Loc::new(1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_trace_loop_not_closed() {
+        let pipes: Collection<Pipe> = Collection::from_puzzle_input("S-7\n..|\n...\n");
+        assert_eq!(pipes.trace_loop(Loc::new(0, 0)), None);
+    }
+
+    #[test_case(include_str!("./data/pipes_sample.txt"), 1)]
+    #[test_case(include_str!("./data/pipes_enclosed_sample.txt"), 8)]
+    #[test_case("..........\n.S------7.\n.|F----7|.\n.||....||.\n.||....||.\n.|L-7F-J|.\n.|..||..|.\n.L--JL--J.\n..........\n", 4)]
+    fn test_count_enclosed(inp: &str, exp: usize) {
+        let pipes: Collection<Pipe> = Collection::from_puzzle_input(inp);
+        let pipe_loop = pipes.trace_loop(find_start(&pipes)).unwrap();
+        assert_eq!(pipes.count_enclosed_by_parity(&pipe_loop), exp);
+        assert_eq!(pipe_loop.count_enclosed(), exp);
+    }
+
+    #[test]
+    fn test_count_enclosed_y_up() {
+        let convention = CoordinateConvention::default().with_y_axis(YAxis::Up);
+        let pipes: Collection<Pipe, i16> = Collection::from_puzzle_input_with(
+            include_str!("./data/pipes_enclosed_sample.txt"),
+            convention,
+        );
+        let pipe_loop = pipes.trace_loop(find_start(&pipes)).unwrap();
+        assert_eq!(pipes.count_enclosed_by_parity(&pipe_loop), 8);
+        assert_eq!(pipe_loop.count_enclosed(), 8);
+    }
+}
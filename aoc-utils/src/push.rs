@@ -0,0 +1,297 @@
+use crate::{Collection, Coord, Direction, Loc, ParseableCharacters};
+use std::collections::HashSet;
+
+// Tiles in a warehouse-style grid. Walls never move. Anything with linked
+// parts can be pushed, carrying the tiles at those offsets along with it
+// (an empty list for single tile objects, the other half for wide boxes).
+// Everything else is empty floor.
+pub trait Pushable {
+    fn is_wall(&self) -> bool;
+    fn linked_parts(&self) -> Option<Vec<(isize, isize)>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushStep<C = isize> {
+    direction: Direction,
+    pushed: Vec<Loc<C>>,
+    mover: Loc<C>,
+}
+impl<C: Coord> PushStep<C> {
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+    // Where every tile that moved was before the move, furthest along first
+    // and mover last. Empty when the move was blocked.
+    pub fn pushed(&self) -> &[Loc<C>] {
+        &self.pushed
+    }
+    pub fn moved(&self) -> bool {
+        !self.pushed.is_empty()
+    }
+    pub fn mover(&self) -> Loc<C> {
+        self.mover
+    }
+}
+
+pub struct PushEngine<T, C = isize> {
+    grid: Collection<T, C>,
+    mover: Loc<C>,
+}
+impl<T, C> PushEngine<T, C>
+where
+    T: ParseableCharacters + Copy + TryFrom<char> + Pushable,
+    C: Coord,
+{
+    pub fn new(grid: Collection<T, C>, mover: Loc<C>) -> Self {
+        Self { grid, mover }
+    }
+    pub fn grid(&self) -> &Collection<T, C> {
+        &self.grid
+    }
+    pub fn mover(&self) -> Loc<C> {
+        self.mover
+    }
+
+    fn delta(&self, direction: Direction) -> (C, C) {
+        let (dx, dy) = self.grid.convention().delta(direction);
+        (num::cast(dx).unwrap(), num::cast(dy).unwrap())
+    }
+
+    // Every tile that would have to move for the mover to step in
+    // `direction`, or None if anything in the chain is up against a wall.
+    pub fn can_push(&self, direction: Direction) -> Option<Vec<Loc<C>>> {
+        let (dx, dy) = self.delta(direction);
+        let mut seen = HashSet::from([self.mover]);
+        let mut chain = vec![self.mover];
+        let mut i = 0;
+        while let Some(loc) = chain.get(i).copied() {
+            i += 1;
+            let next = loc.get_offset(dx, dy)?;
+            if seen.contains(&next) {
+                continue;
+            }
+            let tile = self.grid.get_tile(next)?.get_type();
+            if tile.is_wall() {
+                return None;
+            }
+            let Some(parts) = tile.linked_parts() else {
+                continue;
+            };
+            for part in std::iter::once(Some(next)).chain(
+                parts
+                    .into_iter()
+                    .map(|(px, py)| next.get_offset(num::cast(px)?, num::cast(py)?)),
+            ) {
+                let part = part?;
+                if seen.insert(part) {
+                    chain.push(part);
+                }
+            }
+        }
+        Some(chain)
+    }
+
+    // Moves the whole chain or nothing. Returns the tiles that moved.
+    pub fn step(&mut self, direction: Direction) -> Vec<Loc<C>> {
+        let Some(mut chain) = self.can_push(direction) else {
+            return vec![];
+        };
+        let (dx, dy) = self.delta(direction);
+        // Furthest along first, so every tile moves into space already cleared
+        chain.sort_by_key(|loc| std::cmp::Reverse(loc.get_x() * dx + loc.get_y() * dy));
+        for loc in &chain {
+            let to = loc.get_offset(dx, dy).unwrap();
+            let from = self.grid.position_of(*loc).unwrap();
+            let to = self.grid.position_of(to).unwrap();
+            let moving = self.grid.0[from].tile_type;
+            self.grid.0[from].tile_type = self.grid.0[to].tile_type;
+            self.grid.0[to].tile_type = moving;
+        }
+        self.mover = self.mover.get_offset(dx, dy).unwrap();
+        chain
+    }
+
+    pub fn run(&mut self, moves: &[Direction]) -> usize {
+        moves.iter().filter(|d| !self.step(**d).is_empty()).count()
+    }
+
+    // Runs `moves` and calls `on_step` with the grid as it stands after each one
+    pub fn run_traced(
+        &mut self,
+        moves: &[Direction],
+        mut on_step: impl FnMut(&PushStep<C>, &Collection<T, C>),
+    ) {
+        for direction in moves {
+            let pushed = self.step(*direction);
+            let step = PushStep {
+                direction: *direction,
+                pushed,
+                mover: self.mover,
+            };
+            on_step(&step, &self.grid);
+        }
+    }
+
+    pub fn trace(&mut self, moves: &[Direction]) -> Vec<PushStep<C>> {
+        let mut steps = vec![];
+        self.run_traced(moves, |step, _| steps.push(step.clone()));
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BadTileTypeError;
+    use std::fmt::Display;
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    enum Warehouse {
+        Wall,
+        Floor,
+        Robot,
+        Box,
+        BoxLeft,
+        BoxRight,
+    }
+    impl Pushable for Warehouse {
+        fn is_wall(&self) -> bool {
+            self == &Warehouse::Wall
+        }
+        fn linked_parts(&self) -> Option<Vec<(isize, isize)>> {
+            match self {
+                Warehouse::Robot | Warehouse::Box => Some(vec![]),
+                Warehouse::BoxLeft => Some(vec![(1, 0)]),
+                Warehouse::BoxRight => Some(vec![(-1, 0)]),
+                Warehouse::Wall | Warehouse::Floor => None,
+            }
+        }
+    }
+    impl Display for Warehouse {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(match self {
+                Warehouse::Wall => "#",
+                Warehouse::Floor => ".",
+                Warehouse::Robot => "@",
+                Warehouse::Box => "O",
+                Warehouse::BoxLeft => "[",
+                Warehouse::BoxRight => "]",
+            })
+        }
+    }
+    impl TryFrom<char> for Warehouse {
+        type Error = BadTileTypeError;
+
+        fn try_from(value: char) -> Result<Self, Self::Error> {
+            match value {
+                '#' => Ok(Warehouse::Wall),
+                '.' => Ok(Warehouse::Floor),
+                '@' => Ok(Warehouse::Robot),
+                'O' => Ok(Warehouse::Box),
+                '[' => Ok(Warehouse::BoxLeft),
+                ']' => Ok(Warehouse::BoxRight),
+                _ => Err(BadTileTypeError),
+            }
+        }
+    }
+    impl ParseableCharacters for Warehouse {
+        fn valid_chars() -> Vec<char> {
+            vec!['#', '.', '@', 'O', '[', ']']
+        }
+    }
+
+    fn parse(inp: &str) -> (PushEngine<Warehouse>, Vec<Direction>) {
+        let (grid, moves) = inp.split_once("\n\n").unwrap();
+        let grid: Collection<Warehouse> = Collection::from_puzzle_input(&format!("{}\n", grid));
+        let robot = *grid
+            .tiles()
+            .iter()
+            .find(|t| t.get_type() == &Warehouse::Robot)
+            .unwrap()
+            .loc();
+        (
+            PushEngine::new(grid, robot),
+            Direction::from_moves(moves).unwrap(),
+        )
+    }
+
+    fn widen(inp: &str) -> String {
+        inp.replace('#', "##")
+            .replace('O', "[]")
+            .replace('.', "..")
+            .replace('@', "@.")
+    }
+
+    fn gps_sum(grid: &Collection<Warehouse>) -> isize {
+        grid.tiles()
+            .iter()
+            .filter(|t| matches!(t.get_type(), Warehouse::Box | Warehouse::BoxLeft))
+            .map(|t| 100 * t.loc().get_y() + t.loc().get_x())
+            .sum()
+    }
+
+    #[test]
+    fn test_push_boxes() {
+        let (mut engine, moves) = parse(include_str!("./data/warehouse_sample.txt"));
+        let moved = engine.run(&moves);
+        assert_eq!(
+            engine.grid().to_string(),
+            "########\n#....OO#\n##.....#\n#.....O#\n#.#O@..#\n#...O..#\n#...O..#\n########\n"
+        );
+        assert_eq!(moved, 10);
+        assert_eq!(engine.mover(), Loc::new(4, 4));
+        assert_eq!(gps_sum(engine.grid()), 2028);
+    }
+
+    #[test]
+    fn test_push_wide_boxes() {
+        let inp = widen(include_str!("./data/wide_warehouse_sample.txt"));
+        let (mut engine, moves) = parse(&inp);
+        engine.run(&moves);
+        assert_eq!(
+            engine.grid().to_string(),
+            "##############\n##...[].##..##\n##...@.[]...##\n##....[]....##\n##..........##\n##..........##\n##############\n"
+        );
+        assert_eq!(gps_sum(engine.grid()), 618);
+    }
+
+    #[test]
+    fn test_wide_push_is_atomic() {
+        // The upper box is up against a wall, so neither box may move
+        let inp = "######\n#....#\n#.[].#\n#..[]#\n#..@##\n######\n\n^";
+        let (mut engine, moves) = parse(inp);
+        assert_eq!(engine.can_push(Direction::North).map(|c| c.len()), Some(5));
+        let inp = "######\n#..#.#\n#.[].#\n#..[]#\n#..@.#\n######\n\n^";
+        let (mut blocked, _) = parse(inp);
+        assert_eq!(blocked.can_push(Direction::North), None);
+        let before = blocked.grid().to_string();
+        assert!(blocked.step(Direction::North).is_empty());
+        assert_eq!(blocked.grid().to_string(), before);
+        engine.run(&moves);
+        assert_eq!(
+            engine.grid().to_string(),
+            "######\n#.[].#\n#..[]#\n#..@.#\n#...##\n######\n"
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let (mut engine, moves) = parse(include_str!("./data/warehouse_sample.txt"));
+        let mut renders = vec![];
+        engine.run_traced(&moves[..2], |_, grid| renders.push(grid.to_string()));
+        assert_eq!(
+            renders[0],
+            include_str!("./data/warehouse_sample.txt")
+                .split("\n\n")
+                .next()
+                .unwrap()
+                .to_owned()
+                + "\n"
+        );
+        let steps = engine.trace(&moves[2..4]);
+        assert_eq!(steps[0].direction(), Direction::North);
+        assert!(!steps[0].moved());
+        assert_eq!(steps[1].pushed(), &[Loc::new(3, 1), Loc::new(2, 1)]);
+        assert_eq!(steps[1].mover(), Loc::new(3, 1));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{Collection, Coord, ParseableCharacters, Row};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    // A mirror line between two rows
+    Horizontal,
+    // A mirror line between two columns
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reflection<C = isize> {
+    axis: MirrorAxis,
+    before: C,
+    lines_before: usize,
+    mismatches: usize,
+}
+impl<C: Coord> Reflection<C> {
+    pub fn axis(&self) -> MirrorAxis {
+        self.axis
+    }
+    // The row or column just before (above or left of) the mirror line
+    pub fn before(&self) -> C {
+        self.before
+    }
+    pub fn lines_before(&self) -> usize {
+        self.lines_before
+    }
+    pub fn mismatches(&self) -> usize {
+        self.mismatches
+    }
+}
+
+fn count_mismatches<T: PartialEq, C>(a: Row<T, C>, b: Row<T, C>) -> usize {
+    a.into_iter().zip(b).filter(|(a, b)| a != b).count()
+}
+
+impl<T, C> Collection<T, C>
+where
+    T: ParseableCharacters + Copy + TryFrom<char> + PartialEq,
+    C: Coord,
+{
+    // Every mirror line whose reflected tiles differ in at most `max_smudges`
+    // places, rows first, each in order.
+    pub fn find_reflections(&self, max_smudges: usize) -> Vec<Reflection<C>> {
+        let Some(bounds) = self.bounds() else {
+            return vec![];
+        };
+        let mut reflections = self.find_axis_reflections(
+            MirrorAxis::Horizontal,
+            bounds.min().get_y(),
+            bounds.height(),
+            max_smudges,
+            |y| self.get_row(y),
+        );
+        reflections.extend(self.find_axis_reflections(
+            MirrorAxis::Vertical,
+            bounds.min().get_x(),
+            bounds.width(),
+            max_smudges,
+            |x| self.get_column(x),
+        ));
+        reflections
+    }
+
+    fn find_axis_reflections<'a>(
+        &'a self,
+        axis: MirrorAxis,
+        start: C,
+        len: usize,
+        max_smudges: usize,
+        get_line: impl Fn(C) -> Row<'a, T, C>,
+    ) -> Vec<Reflection<C>> {
+        let line_at = |i: usize| get_line(start + num::cast(i).unwrap());
+        (1..len)
+            .filter_map(|lines_before| {
+                let mut mismatches = 0;
+                for offset in 0..lines_before.min(len - lines_before) {
+                    mismatches += count_mismatches(
+                        line_at(lines_before - 1 - offset),
+                        line_at(lines_before + offset),
+                    );
+                    if mismatches > max_smudges {
+                        return None;
+                    }
+                }
+                Some(Reflection {
+                    axis,
+                    before: start + num::cast(lines_before - 1).unwrap(),
+                    lines_before,
+                    mismatches,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_collection_group, tests::LavaTile, Loc, Tile};
+    use test_case::test_case;
+
+    fn summarize(collection: &Collection<LavaTile>, smudges: usize) -> usize {
+        collection
+            .find_reflections(smudges)
+            .iter()
+            .filter(|r| r.mismatches() == smudges)
+            .map(|r| match r.axis() {
+                MirrorAxis::Horizontal => 100 * r.lines_before(),
+                MirrorAxis::Vertical => r.lines_before(),
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_find_reflections() {
+        let inp = include_str!("./data/lava_sample.txt");
+        let patterns = parse_collection_group::<LavaTile>(inp).unwrap().1;
+        assert_eq!(
+            patterns[0].find_reflections(0),
+            vec![Reflection {
+                axis: MirrorAxis::Vertical,
+                before: 4,
+                lines_before: 5,
+                mismatches: 0
+            }]
+        );
+        assert_eq!(
+            patterns[1].find_reflections(1),
+            vec![
+                Reflection {
+                    axis: MirrorAxis::Horizontal,
+                    before: 0,
+                    lines_before: 1,
+                    mismatches: 1
+                },
+                Reflection {
+                    axis: MirrorAxis::Horizontal,
+                    before: 3,
+                    lines_before: 4,
+                    mismatches: 0
+                },
+            ]
+        );
+    }
+
+    #[test_case(0, 405)]
+    #[test_case(1, 400)]
+    fn test_summarize_reflections(smudges: usize, exp: usize) {
+        let inp = include_str!("./data/lava_sample.txt");
+        let patterns = parse_collection_group::<LavaTile>(inp).unwrap().1;
+        let actual: usize = patterns.iter().map(|p| summarize(p, smudges)).sum();
+        assert_eq!(actual, exp);
+    }
+
+    #[test]
+    fn test_reflection_row_mismatches() {
+        let inp = include_str!("./data/lava_sample.txt");
+        let patterns = parse_collection_group::<LavaTile>(inp).unwrap().1;
+        let row = patterns[0].get_row(0);
+        assert_eq!(
+            row.into_iter().next(),
+            Some(&Tile::new(LavaTile::Rocks, Loc::new(0, 0)))
+        );
+        assert_eq!(
+            count_mismatches(patterns[0].get_row(0), patterns[0].get_row(1)),
+            5
+        );
+    }
+}
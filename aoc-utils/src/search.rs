@@ -0,0 +1,290 @@
+use num::PrimInt;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    hash::Hash,
+};
+
+// Every cheapest way from a start state to a goal state. States are stored
+// once each, with links back to all the states that reach them at their best
+// cost, so overlapping paths stay cheap until they are enumerated.
+#[derive(Debug, Clone)]
+pub struct OptimalPaths<S, K> {
+    cost: K,
+    states: Vec<S>,
+    parents: Vec<Vec<usize>>,
+    goals: Vec<usize>,
+}
+
+impl<S: Clone + Eq + Hash, K: PrimInt> OptimalPaths<S, K> {
+    pub fn cost(&self) -> K {
+        self.cost
+    }
+    pub fn goals(&self) -> Vec<&S> {
+        self.goals.iter().map(|i| &self.states[*i]).collect()
+    }
+
+    // One of the optimal paths, start to goal
+    pub fn path(&self) -> Vec<S> {
+        let mut path = vec![];
+        let mut current = self.goals.first().copied();
+        while let Some(i) = current {
+            path.push(self.states[i].clone());
+            current = self.parents[i].first().copied();
+        }
+        path.reverse();
+        path
+    }
+
+    // Every optimal path, start to goal. There can be exponentially many of
+    // these; use `states` if only the tiles involved matter.
+    pub fn paths(&self) -> Vec<Vec<S>> {
+        let mut paths = vec![];
+        let mut stack: Vec<Vec<usize>> = self.goals.iter().map(|i| vec![*i]).collect();
+        while let Some(partial) = stack.pop() {
+            let last = *partial.last().unwrap();
+            if self.parents[last].is_empty() {
+                paths.push(
+                    partial
+                        .iter()
+                        .rev()
+                        .map(|i| self.states[*i].clone())
+                        .collect(),
+                );
+                continue;
+            }
+            for parent in &self.parents[last] {
+                let mut next = partial.clone();
+                next.push(*parent);
+                stack.push(next);
+            }
+        }
+        paths
+    }
+
+    // Each state that lies on at least one optimal path
+    pub fn states(&self) -> Vec<&S> {
+        let mut seen: HashSet<usize> = self.goals.iter().copied().collect();
+        let mut stack = self.goals.clone();
+        while let Some(i) = stack.pop() {
+            for parent in &self.parents[i] {
+                if seen.insert(*parent) {
+                    stack.push(*parent);
+                }
+            }
+        }
+        let mut on_path: Vec<usize> = seen.into_iter().collect();
+        on_path.sort();
+        on_path.into_iter().map(|i| &self.states[i]).collect()
+    }
+}
+
+// Dijkstra over whatever state the caller needs: (Loc, Direction) for a
+// maze where turning costs extra, (Loc, Direction, run) for a cart that
+// can't go straight too long, and so on. `successors` yields each next state
+// with the cost of stepping to it. Returns None when no goal is reachable.
+pub fn search<S, K, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<OptimalPaths<S, K>>
+where
+    S: Clone + Eq + Hash,
+    K: PrimInt,
+    I: IntoIterator<Item = (S, K)>,
+{
+    let mut nodes = Nodes::default();
+    let mut queue = BinaryHeap::new();
+    for start in starts {
+        let i = nodes.id(start);
+        nodes.best[i] = K::zero();
+        queue.push(Reverse((K::zero(), i)));
+    }
+
+    let mut found: Option<K> = None;
+    let mut goals = vec![];
+    while let Some(Reverse((cost, i))) = queue.pop() {
+        if found.is_some_and(|f| cost > f) {
+            break;
+        }
+        if nodes.settled[i] || cost > nodes.best[i] {
+            continue;
+        }
+        nodes.settled[i] = true;
+        if is_goal(&nodes.states[i]) {
+            found = Some(cost);
+            goals.push(i);
+            continue;
+        }
+        for (next, step) in successors(&nodes.states[i]) {
+            let next_cost = cost + step;
+            let j = nodes.id(next);
+            if next_cost < nodes.best[j] {
+                nodes.best[j] = next_cost;
+                nodes.parents[j] = vec![i];
+                queue.push(Reverse((next_cost, j)));
+            } else if next_cost == nodes.best[j] && !nodes.parents[j].contains(&i) {
+                nodes.parents[j].push(i);
+            }
+        }
+    }
+    found.map(|cost| OptimalPaths {
+        cost,
+        states: nodes.states,
+        parents: nodes.parents,
+        goals,
+    })
+}
+
+// Search bookkeeping, indexed by the order states were first seen
+struct Nodes<S, K> {
+    index: HashMap<S, usize>,
+    states: Vec<S>,
+    best: Vec<K>,
+    parents: Vec<Vec<usize>>,
+    settled: Vec<bool>,
+}
+
+impl<S, K> Default for Nodes<S, K> {
+    fn default() -> Self {
+        Self {
+            index: HashMap::new(),
+            states: vec![],
+            best: vec![],
+            parents: vec![],
+            settled: vec![],
+        }
+    }
+}
+
+impl<S: Clone + Eq + Hash, K: PrimInt> Nodes<S, K> {
+    fn id(&mut self, state: S) -> usize {
+        *self.index.entry(state.clone()).or_insert_with(|| {
+            self.states.push(state);
+            self.best.push(K::max_value());
+            self.parents.push(vec![]);
+            self.settled.push(false);
+            self.states.len() - 1
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::Maze, BadTileTypeError, Collection, Direction, Loc, ParseableCharacters, Turn,
+    };
+    use itertools::Itertools;
+    use test_case::test_case;
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    struct HeatLoss(u32);
+    impl TryFrom<char> for HeatLoss {
+        type Error = BadTileTypeError;
+
+        fn try_from(value: char) -> Result<Self, Self::Error> {
+            value.to_digit(10).map(HeatLoss).ok_or(BadTileTypeError)
+        }
+    }
+    impl ParseableCharacters for HeatLoss {
+        fn valid_chars() -> Vec<char> {
+            ('0'..='9').collect()
+        }
+    }
+
+    fn find(maze: &Collection<Maze>, tile_type: Maze) -> Loc {
+        *maze
+            .tiles()
+            .iter()
+            .find(|t| t.get_type() == &tile_type)
+            .unwrap()
+            .loc()
+    }
+
+    fn reindeer(maze: &Collection<Maze>) -> Option<OptimalPaths<(Loc, Direction), u32>> {
+        let start = find(maze, Maze::Start);
+        let end = find(maze, Maze::End);
+        search(
+            [(start, Direction::East)],
+            |(loc, heading)| {
+                let forward = maze
+                    .get_nearby_tile(*loc, *heading, 1)
+                    .filter(|t| t.get_type() != &Maze::Wall)
+                    .map(|t| ((*t.loc(), *heading), 1));
+                [Turn::Left, Turn::Right]
+                    .into_iter()
+                    .map(|turn| ((*loc, heading.turn(turn)), 1000))
+                    .chain(forward)
+                    .collect_vec()
+            },
+            |(loc, _)| *loc == end,
+        )
+    }
+
+    #[test]
+    fn test_reindeer_maze() {
+        let maze = Collection::from_puzzle_input(include_str!("./data/reindeer_sample.txt"));
+        let found = reindeer(&maze).unwrap();
+        assert_eq!(found.cost(), 7036);
+        assert_eq!(found.path().first().unwrap().0, find(&maze, Maze::Start));
+        let tiles = found.states().into_iter().map(|(loc, _)| loc).unique();
+        assert_eq!(tiles.count(), 45);
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let maze = Collection::from_puzzle_input("S#E\n");
+        assert!(reindeer(&maze).is_none());
+    }
+
+    #[test]
+    fn test_all_paths() {
+        let grid: Collection<Maze> = Collection::from_puzzle_input("...\n...\n...\n");
+        let found: OptimalPaths<Loc, u8> = search(
+            [Loc::new(0, 0)],
+            |loc| {
+                [Direction::East, Direction::South]
+                    .into_iter()
+                    .filter_map(|d| grid.get_nearby_tile(*loc, d, 1))
+                    .map(|t| (*t.loc(), 1))
+                    .collect_vec()
+            },
+            |loc| *loc == Loc::new(2, 2),
+        )
+        .unwrap();
+        assert_eq!(found.cost(), 4);
+        assert_eq!(found.paths().len(), 6);
+        assert_eq!(found.states().len(), 9);
+        assert!(found.paths().iter().all(|p| p.len() == 5));
+    }
+
+    #[test_case(1, 3, 102)]
+    #[test_case(4, 10, 94)]
+    fn test_crucible(min_run: u8, max_run: u8, exp: u32) {
+        let city: Collection<HeatLoss> =
+            Collection::from_puzzle_input(include_str!("./data/crucible_sample.txt"));
+        let end = city.bounds().unwrap().max();
+        let found = search(
+            [
+                (Loc::new(0, 0), Direction::East, 0),
+                (Loc::new(0, 0), Direction::South, 0),
+            ],
+            |(loc, heading, run)| {
+                [Turn::Left, Turn::Right]
+                    .into_iter()
+                    .map(|turn| (heading.turn(turn), 1))
+                    .filter(|_| *run >= min_run)
+                    .chain((*run < max_run).then_some((*heading, run + 1)))
+                    .filter_map(|(direction, run)| {
+                        let tile = city.get_nearby_tile(*loc, direction, 1)?;
+                        Some(((*tile.loc(), direction, run), tile.get_type().0))
+                    })
+                    .collect_vec()
+            },
+            |(loc, _, run)| *loc == end && *run >= min_run,
+        )
+        .unwrap();
+        assert_eq!(found.cost(), exp);
+    }
+}
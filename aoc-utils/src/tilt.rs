@@ -0,0 +1,192 @@
+use crate::{
+    cycle::repeat_with_cycle_detection, Collection, Coord, Direction, Loc, ParseableCharacters,
+};
+use std::hash::Hash;
+
+impl<T, C> Collection<T, C>
+where
+    T: ParseableCharacters + Copy + TryFrom<char>,
+    C: Coord,
+{
+    // Slides every movable tile as far as it can go in `direction`, stopping at
+    // blocking tiles, other movable tiles or the edge of the grid. Whatever the
+    // movable tile lands on is swapped back into the space it left. Returns how
+    // many tiles moved.
+    pub fn tilt(
+        &mut self,
+        direction: Direction,
+        movable: impl Fn(&T) -> bool,
+        blocking: impl Fn(&T) -> bool,
+    ) -> usize {
+        let Some(bounds) = self.bounds() else {
+            return 0;
+        };
+        let mut positions: Vec<Option<usize>> = vec![None; bounds.area()];
+        for (position, tile) in self.0.iter().enumerate() {
+            positions[bounds.index_of(tile.loc).unwrap()] = Some(position);
+        }
+        let (dx, dy) = self.1.delta(direction);
+        let (dx, dy): (C, C) = (num::cast(dx).unwrap(), num::cast(dy).unwrap());
+        let position_at = |loc: Option<Loc<C>>| positions[bounds.index_of(loc?)?];
+
+        let mut moved = 0;
+        for index in 0..bounds.area() {
+            let start = bounds.loc_at(index).unwrap();
+            if position_at(Some(start)).is_none()
+                || position_at(start.get_offset(C::zero() - dx, C::zero() - dy)).is_some()
+            {
+                continue;
+            }
+            let mut line = vec![];
+            let mut loc = Some(start);
+            while let Some(position) = position_at(loc) {
+                line.push(position);
+                loc = loc.and_then(|l| l.get_offset(dx, dy));
+            }
+
+            let mut landing = line.len();
+            for k in (0..line.len()).rev() {
+                let tile_type = self.0[line[k]].tile_type;
+                if movable(&tile_type) {
+                    landing -= 1;
+                    if landing != k {
+                        self.0[line[k]].tile_type = self.0[line[landing]].tile_type;
+                        self.0[line[landing]].tile_type = tile_type;
+                        moved += 1;
+                    }
+                } else if blocking(&tile_type) {
+                    landing = k;
+                }
+            }
+        }
+        moved
+    }
+
+    // Runs `cycles` rounds of tilting through `directions` in order, skipping
+    // ahead once the grid repeats a previous layout.
+    pub fn tilt_cycles(
+        &mut self,
+        directions: &[Direction],
+        cycles: usize,
+        movable: impl Fn(&T) -> bool,
+        blocking: impl Fn(&T) -> bool,
+    ) where
+        T: Hash + Eq,
+    {
+        let start = std::mem::take(&mut self.0);
+        self.0 = repeat_with_cycle_detection(
+            start,
+            cycles,
+            |tiles| tiles.iter().map(|t| t.tile_type).collect::<Vec<T>>(),
+            |tiles| {
+                let mut collection = Collection(std::mem::take(tiles), self.1);
+                for direction in directions {
+                    collection.tilt(*direction, &movable, &blocking);
+                }
+                *tiles = collection.0;
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BadTileTypeError;
+    use std::fmt::Display;
+    use test_case::test_case;
+
+    #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+    enum RockTile {
+        Round,
+        Cube,
+        Empty,
+    }
+    impl Display for RockTile {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(match self {
+                RockTile::Round => "O",
+                RockTile::Cube => "#",
+                RockTile::Empty => ".",
+            })
+        }
+    }
+    impl TryFrom<char> for RockTile {
+        type Error = BadTileTypeError;
+
+        fn try_from(value: char) -> Result<Self, Self::Error> {
+            match value {
+                'O' => Ok(Self::Round),
+                '#' => Ok(Self::Cube),
+                '.' => Ok(Self::Empty),
+                _ => Err(BadTileTypeError),
+            }
+        }
+    }
+    impl ParseableCharacters for RockTile {
+        fn valid_chars() -> Vec<char> {
+            vec!['O', '#', '.']
+        }
+    }
+
+    fn is_round(t: &RockTile) -> bool {
+        t == &RockTile::Round
+    }
+    fn is_cube(t: &RockTile) -> bool {
+        t == &RockTile::Cube
+    }
+    fn north_load(collection: &Collection<RockTile>) -> isize {
+        let height = collection.count_rows() as isize;
+        collection
+            .tiles()
+            .iter()
+            .filter(|t| is_round(t.get_type()))
+            .map(|t| height - t.loc().get_y())
+            .sum()
+    }
+    fn spin_cycle() -> Vec<Direction> {
+        vec![
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ]
+    }
+
+    #[test]
+    fn test_tilt_north() {
+        let mut rocks: Collection<RockTile> =
+            Collection::from_puzzle_input(include_str!("./data/rocks_sample.txt"));
+        rocks.tilt(Direction::North, is_round, is_cube);
+        assert_eq!(
+            rocks.to_string(),
+            "OOOO.#.O..\nOO..#....#\nOO..O##..O\nO..#.OO...\n........#.\n..#....#.#\n..O..#.O.O\n..O.......\n#....###..\n#....#....\n"
+        );
+        assert_eq!(north_load(&rocks), 136);
+        assert_eq!(rocks.tilt(Direction::North, is_round, is_cube), 0);
+    }
+
+    #[test]
+    fn test_tilt_diagonal() {
+        let mut rocks: Collection<RockTile> = Collection::from_puzzle_input("OO.\n.O.\n#.#\n");
+        rocks.tilt(Direction::SouthEast, is_round, is_cube);
+        assert_eq!(rocks.to_string(), "O..\n.OO\n#.#\n");
+    }
+
+    #[test_case(1, ".....#....\n....#...O#\n...OO##...\n.OO#......\n.....OOO#.\n.O#...O#.#\n....O#....\n......OOOO\n#...O###..\n#..OO#....\n")]
+    #[test_case(3, ".....#....\n....#...O#\n.....##...\n..O#......\n.....OOO#.\n.O#...O#.#\n....O#...O\n.......OOO\n#...O###.O\n#.OOO#...O\n")]
+    fn test_tilt_cycles(cycles: usize, exp: &str) {
+        let mut rocks: Collection<RockTile> =
+            Collection::from_puzzle_input(include_str!("./data/rocks_sample.txt"));
+        rocks.tilt_cycles(&spin_cycle(), cycles, is_round, is_cube);
+        assert_eq!(rocks.to_string(), exp);
+    }
+
+    #[test]
+    fn test_tilt_a_billion_cycles() {
+        let mut rocks: Collection<RockTile> =
+            Collection::from_puzzle_input(include_str!("./data/rocks_sample.txt"));
+        rocks.tilt_cycles(&spin_cycle(), 1_000_000_000, is_round, is_cube);
+        assert_eq!(north_load(&rocks), 64);
+    }
+}
@@ -4,5 +4,8 @@ use shared::{WordSearch, PUZZLE_INPUT};
 fn main() {
     let word_search = WordSearch::from(PUZZLE_INPUT);
     let words = word_search.search_puzzle_for_words();
+    if std::env::args().any(|a| a == "--show") {
+        println!("{}", word_search.render_words(&words, true));
+    }
     println!("{}", words.len());
 }
@@ -1,5 +1,8 @@
 mod shared;
+use shared::{WordSearch, PUZZLE_INPUT};
 
 fn main() {
-
+    let word_search = WordSearch::from(PUZZLE_INPUT);
+    let crosses = word_search.search_puzzle_for_crosses();
+    println!("{}", crosses.len());
 }
@@ -1,5 +1,6 @@
 use aoc_utils::{BadTileTypeError, Collection, Direction, Loc, ParseableCharacters, Tile, parse_collection};
 use rayon::prelude::*;
+use std::{collections::HashMap, fmt::Display};
 
 #[cfg(test)]
 pub const PUZZLE_INPUT: &str = include_str!("../../data/sample_input.txt");
@@ -42,6 +43,16 @@ impl TryFrom<char> for Letter {
         }
     }
 }
+impl Display for Letter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Letter::X => "X",
+            Letter::M => "M",
+            Letter::A => "A",
+            Letter::S => "S",
+        })
+    }
+}
 impl ParseableCharacters for Letter {
     fn valid_chars() -> Vec<char> {
         vec!['X', 'M', 'A', 'S']
@@ -63,6 +74,7 @@ impl From<&str> for WordSearch {
     }
 }
 impl WordSearch {
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn search_puzzle_for_words(&self) -> Vec<Word> {
         self.0.tiles()
             .par_iter()
@@ -100,6 +112,67 @@ impl WordSearch {
             .collect();
         words
     }
+
+    // The grid with every letter that isn't part of a found word blanked
+    // out. Coloured output gives each reading direction its own colour.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn render_words(&self, words: &[Word], coloured: bool) -> String {
+        let mut directions: HashMap<Loc, Direction> = HashMap::new();
+        for word in words {
+            let (first, second) = (word[0].loc(), word[1].loc());
+            let delta = (second.get_x() - first.get_x(), second.get_y() - first.get_y());
+            let direction = Direction::from_delta(delta).unwrap();
+            for tile in word {
+                directions.insert(*tile.loc(), direction);
+            }
+        }
+        self.0.render_with(|t| match directions.get(t.loc()) {
+            Some(d) if coloured => format!("\x1b[{}m{}\x1b[0m", colour_code(d), t),
+            Some(_) => t.to_string(),
+            None => ".".to_string(),
+        })
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn search_puzzle_for_crosses(&self) -> Vec<Loc> {
+        let mut centres: Vec<Loc> = self.0.tiles()
+            .par_iter()
+            .filter(|t| self.is_cross_centre(*t.loc()))
+            .map(|t| *t.loc())
+            .collect();
+        centres.sort_by_key(|l| (l.get_y(), l.get_x()));
+        centres
+    }
+
+    // An A with M and S at opposite ends of both diagonals
+    fn is_cross_centre(&self, loc: Loc) -> bool {
+        if self.0.get_tile(loc).map(|t| *t.get_type()) != Some(Letter::A) {
+            return false;
+        }
+        let letter_at = |d: Direction| {
+            let nearby = loc.get_nearby(d, 1)?;
+            self.0.get_tile(nearby).map(|t| *t.get_type())
+        };
+        [Direction::NorthEast, Direction::NorthWest].iter().all(|d| {
+            matches!(
+                (letter_at(*d), letter_at(d.opposite())),
+                (Some(Letter::M), Some(Letter::S)) | (Some(Letter::S), Some(Letter::M))
+            )
+        })
+    }
+}
+
+fn colour_code(direction: &Direction) -> u8 {
+    match direction {
+        Direction::North => 31,
+        Direction::East => 32,
+        Direction::South => 33,
+        Direction::West => 34,
+        Direction::NorthEast => 35,
+        Direction::SouthEast => 36,
+        Direction::SouthWest => 91,
+        Direction::NorthWest => 92,
+    }
 }
 
 #[cfg(test)]
@@ -127,4 +200,40 @@ mod tests {
         let actual: Vec<Word> = inp.search_puzzle_for_words();
         assert_eq!(actual.len(), 18);
     }
+
+    #[test]
+    fn test_render_words() {
+        let inp = WordSearch::from(PUZZLE_INPUT);
+        let words = inp.search_puzzle_for_words();
+        let actual = inp.render_words(&words, false);
+        assert_eq!(actual, include_str!("../../data/sample_words.txt"));
+    }
+
+    #[test]
+    fn test_render_words_coloured() {
+        let inp = WordSearch::from(PUZZLE_INPUT);
+        let words = inp.search_loc_for_words(Loc::new(5, 9));
+        let actual = inp.render_words(&words, true);
+        assert!(actual.starts_with("..........\n"));
+        assert!(actual.contains("\x1b[32mS\x1b[0m"));
+        assert!(actual.contains("\x1b[35mS\x1b[0m"));
+        assert!(actual.contains("\x1b[92mS\x1b[0m"));
+        assert_eq!(actual.matches("\x1b[0m").count(), 10);
+    }
+
+    #[test]
+    fn test_cross_search_loc() {
+        let inp = WordSearch::from(PUZZLE_INPUT);
+        assert!(inp.is_cross_centre(Loc::new(2, 1)));
+        assert!(!inp.is_cross_centre(Loc::new(9, 1)));
+        assert!(!inp.is_cross_centre(Loc::new(0, 0)));
+    }
+
+    #[test]
+    fn test_cross_search() {
+        let inp = WordSearch::from(PUZZLE_INPUT);
+        let actual: Vec<Loc> = inp.search_puzzle_for_crosses();
+        assert_eq!(actual.len(), 9);
+        assert_eq!(actual[0], Loc::new(2, 1));
+    }
 }
@@ -1,5 +1,26 @@
 mod shared;
+use shared::{check_rules, rules_to_dot, sum_reordered_middles, PUZZLE_INPUT};
 
+// `--check` lists broken rules and rule cycles; `--dot [update]` prints the
+// rule graph for Graphviz
 fn main() {
-
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(|a| a.as_str()) {
+        Some("--check") => print!("{}", check_rules(PUZZLE_INPUT)),
+        Some("--dot") => {
+            let dot = args
+                .get(1)
+                .map(|n| {
+                    n.parse()
+                        .map_err(|_| format!("not an update number: {}", n))
+                })
+                .transpose()
+                .and_then(|highlight| rules_to_dot(PUZZLE_INPUT, highlight));
+            match dot {
+                Ok(dot) => print!("{}", dot),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        _ => println!("{}", sum_reordered_middles(PUZZLE_INPUT)),
+    }
 }
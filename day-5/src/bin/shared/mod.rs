@@ -1,4 +1,9 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{Debug, Display},
+    hash::Hash,
+    str::FromStr,
+};
 
 #[cfg(test)]
 pub const PUZZLE_INPUT: &str = include_str!("../../data/sample_input.txt");
@@ -6,92 +11,486 @@ pub const PUZZLE_INPUT: &str = include_str!("../../data/sample_input.txt");
 #[cfg(not(test))]
 pub const PUZZLE_INPUT: &str = include_str!("../../data/puzzle_input.txt");
 
-fn parse_input(inp: &str) -> (RuleSet, Vec<Update>) {
-    let (rule_inp, update_inp) = inp.split_once("\n\n").expect("Known input");
+// Anything that can label a page: plain numbers, bigger numbers or names
+trait PageId: Clone + Eq + Hash + Ord + Debug + Display + FromStr {}
+impl<P: Clone + Eq + Hash + Ord + Debug + Display + FromStr> PageId for P {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParseError {
+    MalformedRule { line: usize, text: String },
+    MalformedUpdate { line: usize, text: String },
+    MissingSeparator { line: usize },
+}
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::MalformedRule { line, text } => {
+                write!(
+                    f,
+                    "line {}: expected a rule like 47|53, found {:?}",
+                    line, text
+                )
+            }
+            ParseError::MalformedUpdate { line, text } => {
+                write!(
+                    f,
+                    "line {}: expected pages like 75,47,61, found {:?}",
+                    line, text
+                )
+            }
+            ParseError::MissingSeparator { line } => {
+                write!(
+                    f,
+                    "line {}: expected a blank line between rules and updates",
+                    line
+                )
+            }
+        }
+    }
+}
+impl std::error::Error for ParseError {}
+
+fn parse_input<P: PageId>(inp: &str) -> (RuleSet<P>, Vec<Update<P>>) {
+    try_parse_input(inp).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn try_parse_input<P: PageId>(inp: &str) -> Result<(RuleSet<P>, Vec<Update<P>>), ParseError> {
+    let mut lines = inp.lines().enumerate().map(|(i, text)| (i + 1, text));
     let mut ruleset = RuleSet::default();
-    for i in rule_inp.lines() {
-        let (page, dependent) = i.split_once('|').unwrap();
-        ruleset.add_rule(Rule::new(page.parse().unwrap(), dependent.parse().unwrap()));
-    }
-    let updates: Vec<Update> = update_inp
-        .lines()
-        .into_iter()
-        .map(|l| {
-            Update::new(
-                l.split(",")
-                    .into_iter()
-                    .map(|page| page.parse().unwrap())
-                    .collect(),
-            )
+    let mut separated = false;
+    for (line, text) in lines.by_ref() {
+        if text.trim().is_empty() {
+            separated = true;
+            break;
+        }
+        match parse_rule(text) {
+            Some(rule) => ruleset.add_rule(rule),
+            None if text.contains(',') && parse_update::<P>(text).is_some() => {
+                return Err(ParseError::MissingSeparator { line })
+            }
+            None => {
+                return Err(ParseError::MalformedRule {
+                    line,
+                    text: text.to_string(),
+                })
+            }
+        }
+    }
+    if !separated {
+        return Err(ParseError::MissingSeparator {
+            line: inp.lines().count() + 1,
+        });
+    }
+    let updates = lines
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(line, text)| {
+            parse_update(text).ok_or_else(|| ParseError::MalformedUpdate {
+                line,
+                text: text.to_string(),
+            })
         })
-        .collect();
-    (ruleset, updates)
+        .collect::<Result<Vec<Update<P>>, ParseError>>()?;
+    Ok((ruleset, updates))
+}
+
+fn parse_page<P: PageId>(text: &str) -> Option<P> {
+    let text = text.trim();
+    if text.is_empty() || text.contains(['|', ',']) {
+        return None;
+    }
+    text.parse().ok()
+}
+fn parse_rule<P: PageId>(text: &str) -> Option<Rule<P>> {
+    let (page, dependent) = text.split_once('|')?;
+    Some(Rule::new(parse_page(page)?, parse_page(dependent)?))
+}
+fn parse_update<P: PageId>(text: &str) -> Option<Update<P>> {
+    let pages = text
+        .split(',')
+        .map(parse_page)
+        .collect::<Option<Vec<P>>>()?;
+    Some(Update::new(pages))
 }
 
+#[cfg_attr(not(test), allow(dead_code))]
 pub fn sum_valid_middles(inp: &str) -> usize {
-    let (ruleset, updates) = parse_input(inp);
+    let (ruleset, updates) = parse_input::<usize>(inp);
     updates
         .iter()
         .filter(|u| u.is_valid(&ruleset))
-        .map(|u| usize::from(u.get_middle()))
+        .map(|u| u.get_middle())
+        .sum()
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn sum_reordered_middles(inp: &str) -> usize {
+    let (ruleset, updates) = parse_input::<usize>(inp);
+    updates
+        .iter()
+        .filter(|u| !u.is_valid(&ruleset))
+        .map(|u| {
+            u.reordered(&ruleset)
+                .expect("Rules for update form a cycle")
+        })
+        .map(|u| u.get_middle())
         .sum()
 }
 
-struct Rule {
-    page: u8,
-    dependent: u8,
+// Everything wrong with the input: parse errors, cycles in the rules, and
+// each broken rule in each update. Updates are numbered from 1.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn check_rules(inp: &str) -> String {
+    let (ruleset, updates) = match try_parse_input::<usize>(inp) {
+        Ok(parsed) => parsed,
+        Err(e) => return format!("{}\n", e),
+    };
+    let mut out = String::new();
+    for cycle in ruleset.find_cycles() {
+        out.push_str(&format!("rules cycle: {}\n", describe_cycle(&cycle)));
+    }
+    for (i, update) in updates.iter().enumerate() {
+        let report = update.validate(&ruleset);
+        for cycle in report.cycles {
+            out.push_str(&format!(
+                "update {}: cycle {}\n",
+                i + 1,
+                describe_cycle(&cycle)
+            ));
+        }
+        for violation in report.violations {
+            out.push_str(&format!("update {}: {}\n", i + 1, violation));
+        }
+    }
+    out
+}
+
+// Back round to the first page, so "1 -> 2 -> 1" rather than a lone rule
+fn describe_cycle<P: Display>(cycle: &[P]) -> String {
+    cycle
+        .iter()
+        .chain(cycle.first())
+        .map(|page| page.to_string())
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
+
+// Reduced Graphviz source for the rules, optionally highlighting an update
+// numbered from 1
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn rules_to_dot(inp: &str, highlight: Option<usize>) -> Result<String, String> {
+    let (ruleset, updates) = try_parse_input::<usize>(inp).map_err(|e| e.to_string())?;
+    let update = match highlight {
+        Some(n) => Some(
+            n.checked_sub(1)
+                .and_then(|i| updates.get(i))
+                .ok_or_else(|| format!("no update {}", n))?,
+        ),
+        None => None,
+    };
+    Ok(ruleset.to_dot(update, true))
+}
+
+// Quoted so names with dashes or spaces are still valid Graphviz IDs
+fn dot_id<P: Display>(page: &P) -> String {
+    format!("\"{}\"", page.to_string().replace('"', "\\\""))
+}
+
+struct Rule<P = u8> {
+    page: P,
+    dependent: P,
 }
 
-impl Rule {
-    fn new(page: u8, dependent: u8) -> Self {
+impl<P> Rule<P> {
+    fn new(page: P, dependent: P) -> Self {
         Self { page, dependent }
     }
 }
 
-#[derive(Default, Debug, Eq, PartialEq)]
-struct RuleSet(HashMap<u8, Vec<u8>>);
-impl RuleSet {
-    fn add_rule(&mut self, rule: Rule) {
-        if !self.0.contains_key(&rule.page) {
-            self.0.insert(rule.page, vec![]);
-        }
-        let _ = &mut self.0.get_mut(&rule.page).unwrap().push(rule.dependent);
+#[derive(Debug)]
+struct RuleSet<P = u8>(HashMap<P, Vec<P>>);
+impl<P> Default for RuleSet<P> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+impl<P: Eq + Hash> PartialEq for RuleSet<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl<P: Eq + Hash> Eq for RuleSet<P> {}
+impl<P: PageId> RuleSet<P> {
+    fn add_rule(&mut self, rule: Rule<P>) {
+        self.0.entry(rule.page).or_default().push(rule.dependent);
     }
+    #[cfg_attr(not(test), allow(dead_code))]
     fn len(&self) -> usize {
         self.0.len()
     }
-    fn get_rule(&self, page: u8) -> Option<&Vec<u8>> {
-        self.0.get(&page)
+    fn get_rule(&self, page: &P) -> Option<&Vec<P>> {
+        self.0.get(page)
+    }
+    fn pages(&self) -> Vec<P> {
+        let mut pages: Vec<P> = self
+            .0
+            .iter()
+            .flat_map(|(page, dependents)| dependents.iter().chain([page]))
+            .cloned()
+            .collect::<HashSet<P>>()
+            .into_iter()
+            .collect();
+        pages.sort();
+        pages
+    }
+
+    // Only the rules where both pages are in `pages`
+    fn restricted_to(&self, pages: &[P]) -> RuleSet<P> {
+        let mut ruleset = RuleSet::default();
+        for (page, dependents) in self.0.iter().filter(|(page, _)| pages.contains(page)) {
+            for dependent in dependents.iter().filter(|d| pages.contains(d)) {
+                ruleset.add_rule(Rule::new(page.clone(), dependent.clone()));
+            }
+        }
+        ruleset
+    }
+
+    // Pages that can all reach each other, in order of their lowest page.
+    // A page outside any cycle is a group on its own.
+    fn groups(&self) -> Vec<HashSet<P>> {
+        let mut groups: Vec<HashSet<P>> = vec![];
+        for start in self.pages() {
+            if groups.iter().any(|group| group.contains(&start)) {
+                continue;
+            }
+            let group = self
+                .reachable_from(&start)
+                .into_iter()
+                .filter(|page| self.reachable_from(page).contains(&start))
+                .chain([start.clone()])
+                .collect();
+            groups.push(group);
+        }
+        groups
+    }
+
+    // One cycle from each strongly connected group of pages, starting on the
+    // group's lowest page. The rule leading back to that page is implied
+    // rather than repeated. Pages in a cycle have no consistent order, so
+    // any sort involving them is meaningless.
+    fn find_cycles(&self) -> Vec<Vec<P>> {
+        self.groups()
+            .iter()
+            .filter_map(|group| self.shortest_cycle(group.iter().min().unwrap(), group))
+            .collect()
+    }
+    fn edges(&self) -> Vec<(P, P)> {
+        let mut edges: Vec<(P, P)> = self
+            .0
+            .iter()
+            .flat_map(|(page, dependents)| dependents.iter().map(|d| (page.clone(), d.clone())))
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+    // An edge is redundant when its pages are already ordered by a longer
+    // route. Pages in a cycle all order each other, so routes are compared
+    // between groups: edges inside a group are never dropped.
+    fn is_redundant(&self, page: &P, dependent: &P, group_of: &HashMap<P, usize>) -> bool {
+        let (from, to) = (group_of[page], group_of[dependent]);
+        from != to
+            && self
+                .0
+                .iter()
+                .filter(|(other, _)| group_of[*other] == from)
+                .flat_map(|(_, dependents)| dependents)
+                .any(|via| {
+                    ![from, to].contains(&group_of[via])
+                        && self.reachable_from(via).contains(dependent)
+                })
+    }
+
+    // Graphviz source for the rule graph. Highlighting an update draws only
+    // the rules between its pages, with the ones it breaks in red; reducing
+    // drops rules implied by others so big rule sets stay readable.
+    fn to_dot(&self, highlight: Option<&Update<P>>, reduce: bool) -> String {
+        let restricted;
+        let graph = match highlight {
+            Some(update) => {
+                restricted = self.restricted_to(&update.pages);
+                &restricted
+            }
+            None => self,
+        };
+        let groups = graph.groups();
+        let mut dot = String::from("digraph rules {\n");
+        for group in groups.iter().filter(|group| reduce && group.len() > 1) {
+            dot.push_str(&format!(
+                "    // {} pages form a cycle, so the rules between them aren't reduced\n",
+                group.len()
+            ));
+        }
+        let group_of: HashMap<P, usize> = groups
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, group)| group.into_iter().map(move |page| (page, i)))
+            .collect();
+        let violations = highlight.map_or(vec![], |u| u.violations(graph));
+        for page in highlight.into_iter().flat_map(|u| &u.pages) {
+            dot.push_str(&format!(
+                "    {} [style=filled, fillcolor=lightblue];\n",
+                dot_id(page)
+            ));
+        }
+        for (page, dependent) in graph.edges() {
+            let violated = violations
+                .iter()
+                .any(|v| v.page == page && v.dependent == dependent);
+            if violated {
+                dot.push_str(&format!(
+                    "    {} -> {} [color=red];\n",
+                    dot_id(&page),
+                    dot_id(&dependent)
+                ));
+            } else if !reduce || !graph.is_redundant(&page, &dependent, &group_of) {
+                dot.push_str(&format!(
+                    "    {} -> {};\n",
+                    dot_id(&page),
+                    dot_id(&dependent)
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+    fn reachable_from(&self, start: &P) -> HashSet<P> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![start.clone()];
+        while let Some(page) = stack.pop() {
+            for dependent in self.get_rule(&page).into_iter().flatten() {
+                if seen.insert(dependent.clone()) {
+                    stack.push(dependent.clone());
+                }
+            }
+        }
+        seen
+    }
+    fn shortest_cycle(&self, start: &P, group: &HashSet<P>) -> Option<Vec<P>> {
+        let mut came_from: HashMap<P, P> = HashMap::new();
+        let mut queue = VecDeque::from([start.clone()]);
+        while let Some(page) = queue.pop_front() {
+            for dependent in self.get_rule(&page).into_iter().flatten() {
+                if dependent == start {
+                    let mut cycle = vec![page];
+                    while let Some(previous) = came_from.get(cycle.last().unwrap()) {
+                        cycle.push(previous.clone());
+                    }
+                    cycle.reverse();
+                    return Some(cycle);
+                }
+                if group.contains(dependent) && !came_from.contains_key(dependent) {
+                    came_from.insert(dependent.clone(), page.clone());
+                    queue.push_back(dependent.clone());
+                }
+            }
+        }
+        None
+    }
+}
+
+// `page` has to be printed before `dependent`, but the update has them the
+// other way round. Positions are indexes into the update's pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Violation<P = u8> {
+    page: P,
+    page_position: usize,
+    dependent: P,
+    dependent_position: usize,
+}
+impl<P: Display> Display for Violation<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at position {} must come before {} at position {}",
+            self.page, self.page_position, self.dependent, self.dependent_position
+        )
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct Update {
-    pages: Vec<u8>,
-    ruleset: RuleSet
-}
-impl Update {
-    fn new(pages: Vec<u8>) -> Self {
-        Self { pages, ruleset: RuleSet::default() }
-    }
-
-    fn is_valid(&self, ruleset: &RuleSet) -> bool {
-        let mut processed_pages: Vec<u8> = vec![];
-        for page in &self.pages {
-            if let Some(dependents) = ruleset.get_rule(*page) {
-                for dependent_page in dependents {
-                    if processed_pages.contains(dependent_page) {
-                        return false;
-                    }
+struct ValidationReport<P = u8> {
+    violations: Vec<Violation<P>>,
+    cycles: Vec<Vec<P>>,
+}
+impl<P> ValidationReport<P> {
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Update<P = u8> {
+    pages: Vec<P>,
+}
+impl<P: PageId> Update<P> {
+    fn new(pages: Vec<P>) -> Self {
+        Self { pages }
+    }
+
+    fn is_valid(&self, ruleset: &RuleSet<P>) -> bool {
+        self.violations(ruleset).is_empty()
+    }
+
+    fn violations(&self, ruleset: &RuleSet<P>) -> Vec<Violation<P>> {
+        let mut violations = vec![];
+        for (page_position, page) in self.pages.iter().enumerate() {
+            for dependent in ruleset.get_rule(page).into_iter().flatten() {
+                let earlier = self.pages[..page_position]
+                    .iter()
+                    .position(|p| p == dependent);
+                if let Some(dependent_position) = earlier {
+                    violations.push(Violation {
+                        page: page.clone(),
+                        page_position,
+                        dependent: dependent.clone(),
+                        dependent_position,
+                    });
                 }
             }
-            processed_pages.push(*page)
         }
-        true
+        violations
+    }
+
+    // Every broken rule, plus any cycles among the rules for these pages
+    fn validate(&self, ruleset: &RuleSet<P>) -> ValidationReport<P> {
+        ValidationReport {
+            violations: self.violations(ruleset),
+            cycles: ruleset.restricted_to(&self.pages).find_cycles(),
+        }
+    }
+
+    // Topological sort over just this update's pages, so rules about pages
+    // that aren't present don't get in the way. Ties keep their original
+    // order. None if the rules between these pages go round in a circle.
+    fn reordered(&self, ruleset: &RuleSet<P>) -> Option<Update<P>> {
+        let mut remaining = self.pages.clone();
+        let mut pages = vec![];
+        while !remaining.is_empty() {
+            let next = remaining.iter().position(|page| {
+                !remaining.iter().any(|other| {
+                    ruleset
+                        .get_rule(other)
+                        .is_some_and(|dependents| dependents.contains(page))
+                })
+            })?;
+            pages.push(remaining.remove(next));
+        }
+        Some(Update::new(pages))
     }
 
-    fn get_middle(&self) -> u8 {
-        self.pages[(self.pages.len() - 1) / 2]
+    fn get_middle(&self) -> P {
+        self.pages[(self.pages.len() - 1) / 2].clone()
     }
 }
 
@@ -104,9 +503,9 @@ mod tests {
     fn test_parse_input() {
         let (ruleset, updates) = parse_input(PUZZLE_INPUT);
         assert_eq!(ruleset.len(), 6);
-        assert_eq!(ruleset.get_rule(47), Some(&vec![53, 13, 61, 29]));
-        assert_eq!(ruleset.get_rule(29), Some(&vec![13]));
-        assert_eq!(ruleset.get_rule(61), Some(&vec![13, 53, 29]));
+        assert_eq!(ruleset.get_rule(&47), Some(&vec![53, 13, 61, 29]));
+        assert_eq!(ruleset.get_rule(&29), Some(&vec![13]));
+        assert_eq!(ruleset.get_rule(&61), Some(&vec![13, 53, 29]));
         assert_eq!(updates.len(), 6);
         assert_eq!(updates[0], Update::new(vec![75, 47, 61, 53, 29]));
     }
@@ -137,4 +536,232 @@ mod tests {
         let actual = sum_valid_middles(PUZZLE_INPUT);
         assert_eq!(actual, 143)
     }
+
+    #[test_case(Update::new(vec![75, 47, 61, 53, 29]), vec![75, 47, 61, 53, 29])]
+    #[test_case(Update::new(vec![75, 97, 47, 61, 53]), vec![97, 75, 47, 61, 53])]
+    #[test_case(Update::new(vec![61, 13, 29]), vec![61, 29, 13])]
+    #[test_case(Update::new(vec![97, 13, 75, 29, 47]), vec![97, 75, 47, 29, 13])]
+    fn test_reordered(inp: Update, expected: Vec<u8>) {
+        let (ruleset, _) = parse_input(PUZZLE_INPUT);
+        let actual = inp.reordered(&ruleset).unwrap();
+        assert!(actual.is_valid(&ruleset));
+        assert_eq!(actual, Update::new(expected));
+    }
+
+    #[test]
+    fn test_reordered_cycle() {
+        let mut ruleset = RuleSet::default();
+        ruleset.add_rule(Rule::new(1, 2));
+        ruleset.add_rule(Rule::new(2, 3));
+        ruleset.add_rule(Rule::new(3, 1));
+        assert_eq!(Update::new(vec![3, 2, 1]).reordered(&ruleset), None);
+        assert_eq!(
+            Update::new(vec![3, 1]).reordered(&ruleset),
+            Some(Update::new(vec![3, 1]))
+        );
+    }
+
+    #[test]
+    fn test_violations() {
+        let (ruleset, _) = parse_input(PUZZLE_INPUT);
+        let report = Update::new(vec![97, 13, 75, 29, 47]).validate(&ruleset);
+        assert!(!report.is_valid());
+        assert!(report.cycles.is_empty());
+        assert_eq!(report.violations.len(), 4);
+        assert_eq!(
+            report.violations[0],
+            Violation {
+                page: 75,
+                page_position: 2,
+                dependent: 13,
+                dependent_position: 1
+            }
+        );
+        assert_eq!(
+            report.violations[0].to_string(),
+            "75 at position 2 must come before 13 at position 1"
+        );
+        assert!(Update::new(vec![75, 47, 61, 53, 29])
+            .validate(&ruleset)
+            .is_valid());
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let (mut ruleset, _) = parse_input(PUZZLE_INPUT);
+        assert!(ruleset.find_cycles().is_empty());
+        ruleset.add_rule(Rule::new(13, 97));
+        ruleset.add_rule(Rule::new(99, 99));
+        let cycles = ruleset.find_cycles();
+        assert_eq!(cycles.len(), 2);
+        assert_eq!(cycles[0].len(), 2);
+        assert_eq!(cycles[0][0], 13);
+        assert_eq!(cycles[0][1], 97);
+        assert_eq!(cycles[1], vec![99]);
+        assert_eq!(describe_cycle(&cycles[0]), "13 -> 97 -> 13");
+        assert_eq!(describe_cycle(&cycles[1]), "99 -> 99");
+    }
+
+    #[test]
+    fn test_update_cycles() {
+        let mut ruleset = RuleSet::default();
+        ruleset.add_rule(Rule::new(1, 2));
+        ruleset.add_rule(Rule::new(2, 3));
+        ruleset.add_rule(Rule::new(3, 1));
+        ruleset.add_rule(Rule::new(3, 4));
+        assert_eq!(ruleset.find_cycles(), vec![vec![1, 2, 3]]);
+        let report = Update::new(vec![3, 1, 4]).validate(&ruleset);
+        assert!(report.cycles.is_empty());
+        let report = Update::new(vec![2, 3, 1]).validate(&ruleset);
+        assert_eq!(report.cycles, vec![vec![1, 2, 3]]);
+        assert_eq!(report.violations.len(), 1);
+    }
+
+    fn chain() -> RuleSet {
+        let mut ruleset = RuleSet::default();
+        ruleset.add_rule(Rule::new(1, 2));
+        ruleset.add_rule(Rule::new(2, 3));
+        ruleset.add_rule(Rule::new(1, 3));
+        ruleset
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let expected =
+            "digraph rules {\n    \"1\" -> \"2\";\n    \"1\" -> \"3\";\n    \"2\" -> \"3\";\n}\n";
+        assert_eq!(chain().to_dot(None, false), expected);
+        let expected = "digraph rules {\n    \"1\" -> \"2\";\n    \"2\" -> \"3\";\n}\n";
+        assert_eq!(chain().to_dot(None, true), expected);
+    }
+
+    #[test]
+    fn test_to_dot_highlight() {
+        let update = Update::new(vec![3, 1]);
+        let expected = r#"digraph rules {
+    "3" [style=filled, fillcolor=lightblue];
+    "1" [style=filled, fillcolor=lightblue];
+    "1" -> "3" [color=red];
+}
+"#;
+        assert_eq!(chain().to_dot(Some(&update), true), expected);
+    }
+
+    #[test]
+    fn test_to_dot_reduced_cycle() {
+        let mut ruleset = RuleSet::default();
+        for (page, dependent) in [(1, 2), (2, 3), (3, 1), (1, 3)] {
+            ruleset.add_rule(Rule::new(page, dependent));
+        }
+        let reduced = ruleset.to_dot(None, true);
+        assert!(reduced
+            .contains("    // 3 pages form a cycle, so the rules between them aren't reduced\n"));
+        assert_eq!(reduced.matches(" -> ").count(), 4);
+        for (page, dependent) in [(3, 4), (1, 4), (4, 5), (1, 5)] {
+            ruleset.add_rule(Rule::new(page, dependent));
+        }
+        let reduced = ruleset.to_dot(None, true);
+        assert_eq!(reduced.matches(" -> ").count(), 7);
+        assert!(reduced.contains("    \"1\" -> \"4\";\n    \"2\" -> \"3\";\n    \"3\" -> \"1\";\n    \"3\" -> \"4\";\n    \"4\" -> \"5\";\n"));
+        assert!(!reduced.contains("    \"1\" -> \"5\";\n"));
+    }
+
+    #[test]
+    fn test_to_dot_reduced_sample() {
+        let (ruleset, _) = parse_input::<u8>(PUZZLE_INPUT);
+        let full = ruleset.to_dot(None, false);
+        let reduced = ruleset.to_dot(None, true);
+        assert_eq!(full.matches(" -> ").count(), 21);
+        assert_eq!(reduced.matches(" -> ").count(), 6);
+        assert!(reduced.contains("    \"97\" -> \"75\";\n"));
+    }
+
+    #[test]
+    fn test_large_and_named_pages() {
+        let (ruleset, updates) = parse_input::<u32>("1000|2000\n2000|70000\n\n70000,1000,2000\n");
+        assert_eq!(
+            updates[0].reordered(&ruleset).unwrap().pages,
+            vec![1000, 2000, 70000]
+        );
+        let inp = "intro|body\nbody|outro\nintro|outro\n\nbody,intro,outro\n";
+        let (ruleset, updates) = parse_input::<String>(inp);
+        assert_eq!(updates[0].get_middle(), "intro");
+        assert_eq!(updates[0].violations(&ruleset).len(), 1);
+        assert!(ruleset
+            .to_dot(None, true)
+            .contains("    \"body\" -> \"outro\";\n"));
+    }
+
+    #[test]
+    fn test_to_dot_quotes_names() {
+        let inp = "front-matter|my page\nmy page|\"quoted\"\n\nfront-matter,my page\n";
+        let (ruleset, updates) = parse_input::<String>(inp);
+        let expected = r#"digraph rules {
+    "front-matter" [style=filled, fillcolor=lightblue];
+    "my page" [style=filled, fillcolor=lightblue];
+    "front-matter" -> "my page";
+}
+"#;
+        assert_eq!(ruleset.to_dot(Some(&updates[0]), true), expected);
+        assert!(ruleset
+            .to_dot(None, true)
+            .contains("    \"my page\" -> \"\\\"quoted\\\"\";\n"));
+    }
+
+    #[test_case("47|53\n97-13\n\n75,47\n", ParseError::MalformedRule { line: 2, text: "97-13".to_string() })]
+    #[test_case("47|53\n|13\n\n75,47\n", ParseError::MalformedRule { line: 2, text: "|13".to_string() })]
+    #[test_case("47|53\n\n75,47\n75,x\n", ParseError::MalformedUpdate { line: 4, text: "75,x".to_string() })]
+    #[test_case("47|53\n75,47\n", ParseError::MissingSeparator { line: 2 })]
+    #[test_case("47|53\n97|13\n", ParseError::MissingSeparator { line: 3 })]
+    fn test_parse_errors(inp: &str, expected: ParseError) {
+        assert_eq!(try_parse_input::<u8>(inp).err(), Some(expected));
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        let err = try_parse_input::<u8>("47|53\n97|300\n\n75,47\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 2: expected a rule like 47|53, found \"97|300\""
+        );
+    }
+
+    #[test]
+    fn test_check_rules() {
+        let actual = check_rules(PUZZLE_INPUT);
+        assert_eq!(actual.lines().count(), 6);
+        assert!(
+            actual.starts_with("update 4: 97 at position 1 must come before 75 at position 0\n")
+        );
+        let actual = check_rules("1|2\n2|1\n\n2,1\n");
+        assert_eq!(
+            actual,
+            "rules cycle: 1 -> 2 -> 1\nupdate 1: cycle 1 -> 2 -> 1\nupdate 1: 1 at position 1 must come before 2 at position 0\n"
+        );
+        assert_eq!(
+            check_rules("1|2\n"),
+            "line 2: expected a blank line between rules and updates\n"
+        );
+    }
+
+    #[test]
+    fn test_rules_to_dot() {
+        let actual = rules_to_dot(PUZZLE_INPUT, Some(4)).unwrap();
+        assert!(actual.contains("    \"97\" -> \"75\" [color=red];\n"));
+        assert_eq!(
+            rules_to_dot(PUZZLE_INPUT, Some(7)),
+            Err("no update 7".to_string())
+        );
+        assert_eq!(
+            rules_to_dot(PUZZLE_INPUT, Some(0)),
+            Err("no update 0".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sum_reordered_middles() {
+        let actual = sum_reordered_middles(PUZZLE_INPUT);
+        assert_eq!(actual, 123)
+    }
 }
@@ -1,5 +1,7 @@
 mod shared;
+use shared::{PUZZLE_INPUT, Maze};
 
 fn main() {
-
+    let maze = Maze::new(PUZZLE_INPUT);
+    println!("{}", maze.find_loop_obstructions().len());
 }
@@ -1,4 +1,9 @@
-use aoc_utils::{BadTileTypeError, Collection, Direction, Loc, ParseableCharacters};
+use aoc_utils::{
+    BadTileTypeError, Collection, DirectedLocSet, Direction, Entity, EntityMarker, LayeredGrid,
+    Loc, LocSet, ParseableCharacters,
+};
+use rayon::prelude::*;
+use std::{fmt::Display, str::FromStr};
 
 #[cfg(test)]
 pub const PUZZLE_INPUT: &str = include_str!("../../data/sample_input.txt");
@@ -9,28 +14,29 @@ pub const PUZZLE_INPUT: &str = include_str!("../../data/puzzle_input.txt");
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Tile {
     tile_type: TileType,
-    visited: bool,
 }
 
 impl Tile {
-    fn new(tile_type: TileType, visited: bool) -> Self {
-        Self { tile_type, visited }
-    }
-    fn mark_visited(&mut self) {
-        self.tile_type = TileType::Visited;
-        self.visited = true;
+    fn new(tile_type: TileType) -> Self {
+        Self { tile_type }
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TileType {
     Empty,
     Obstacle,
-    Guard(Direction),
-    Visited,
+}
+impl Display for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self.tile_type {
+            TileType::Empty => ".",
+            TileType::Obstacle => "#",
+        })
+    }
 }
 impl ParseableCharacters for Tile {
     fn valid_chars() -> Vec<char> {
-        vec!['#', '.', '>', '<', '^', 'v']
+        vec!['#', '.']
     }
 }
 impl TryFrom<char> for Tile {
@@ -40,92 +46,319 @@ impl TryFrom<char> for Tile {
         let tile_type = match value {
             '#' => Ok(TileType::Obstacle),
             '.' => Ok(TileType::Empty),
-            '^' => Ok(TileType::Guard(Direction::North)),
-            '>' => Ok(TileType::Guard(Direction::East)),
-            '<' => Ok(TileType::Guard(Direction::West)),
-            'v' => Ok(TileType::Guard(Direction::South)),
-            'X' => Ok(TileType::Visited),
             _ => Err(BadTileTypeError),
         }?;
-        Ok(Tile::new(tile_type, false))
+        Ok(Tile::new(tile_type))
     }
 }
+
+// The puzzle only draws the four cardinal headings, but a 45 degree turning
+// policy leaves the guard facing diagonally
+const GUARD_MARKERS: [(char, Direction); 8] = [
+    ('^', Direction::North),
+    ('>', Direction::East),
+    ('v', Direction::South),
+    ('<', Direction::West),
+    ('↗', Direction::NorthEast),
+    ('↘', Direction::SouthEast),
+    ('↙', Direction::SouthWest),
+    ('↖', Direction::NorthWest),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guard;
+impl EntityMarker for Guard {
+    fn from_marker(marker: char) -> Option<(Self, Option<Direction>)> {
+        GUARD_MARKERS
+            .iter()
+            .find(|(c, _)| *c == marker)
+            .map(|(_, direction)| (Guard, Some(*direction)))
+    }
+    fn to_marker(&self, heading: Option<Direction>) -> char {
+        GUARD_MARKERS
+            .iter()
+            .find(|(_, direction)| Some(*direction) == heading)
+            .map_or('^', |(c, _)| *c)
+    }
+    fn terrain_beneath(&self) -> char {
+        '.'
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MazeError {
+    Empty,
+    BadTile {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    UnevenRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    NoGuard,
+    ExtraGuard {
+        line: usize,
+        column: usize,
+    },
+    GuardNotOnFloor(Loc),
+}
+impl Display for MazeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MazeError::Empty => write!(f, "maze is empty"),
+            MazeError::BadTile {
+                line,
+                column,
+                found,
+            } => write!(f, "line {line}, column {column}: unexpected {found:?}"),
+            MazeError::UnevenRow {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line}: expected {expected} tiles, found {found}"),
+            MazeError::NoGuard => write!(f, "no guard in maze"),
+            MazeError::ExtraGuard { line, column } => {
+                write!(f, "line {line}, column {column}: second guard")
+            }
+            MazeError::GuardNotOnFloor(loc) => write!(f, "guard at {loc} is not on open floor"),
+        }
+    }
+}
+impl std::error::Error for MazeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    LeftMap,
+    Looped,
+    StepLimit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatrolReport {
+    reason: ExitReason,
+    steps: usize,
+    turns: usize,
+    path: Vec<(Loc, Direction)>,
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl PatrolReport {
+    pub fn reason(&self) -> ExitReason {
+        self.reason
+    }
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+    // Every position and heading the guard had, in order, from the start
+    pub fn path(&self) -> &[(Loc, Direction)] {
+        &self.path
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Maze {
-    tiles: Collection<Tile>,
-    guard_loc: Loc,
-    guard_direction: Direction,
-    visited_locs: Vec<Loc>,
+    grid: LayeredGrid<Tile, Guard>,
+    visited_locs: LocSet,
+    turn: fn(&Direction) -> Direction,
 }
 
 impl Maze {
     pub fn new(puzzle_input: &str) -> Self {
-        let tiles: Collection<Tile> = Collection::from_puzzle_input(PUZZLE_INPUT);
-        let guard_tile = tiles
-            .tiles()
-            .iter()
-            .find(|t| {
-                t.get_type().tile_type == TileType::Guard(Direction::West)
-                    || t.get_type().tile_type == TileType::Guard(Direction::East)
-                    || t.get_type().tile_type == TileType::Guard(Direction::North)
-                    || t.get_type().tile_type == TileType::Guard(Direction::South)
-            })
-            .take()
-            .unwrap();
-        let guard_loc = guard_tile.loc().clone();
-        let guard_direction = match guard_tile.get_type().tile_type {
-            TileType::Guard(direction) => direction,
-            _ => unreachable!(),
-        };
+        puzzle_input.parse().unwrap()
+    }
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn from_terrain(
+        terrain: Collection<Tile>,
+        guard_loc: Loc,
+        guard_direction: Direction,
+    ) -> Result<Self, MazeError> {
+        match terrain.get_tile(guard_loc) {
+            Some(tile) if tile.get_type().tile_type != TileType::Obstacle => {}
+            _ => return Err(MazeError::GuardNotOnFloor(guard_loc)),
+        }
+        let guard = Entity::new(Guard, guard_loc, Some(guard_direction));
+        Ok(Self::from_grid(LayeredGrid::new(terrain, vec![guard])))
+    }
+    fn from_grid(grid: LayeredGrid<Tile, Guard>) -> Self {
+        let mut visited_locs = LocSet::new(grid.terrain().bounds().unwrap());
+        visited_locs.insert(grid.entities()[0].loc());
         Self {
-            tiles,
-            guard_loc,
-            guard_direction,
-            visited_locs: vec![guard_loc],
+            grid,
+            visited_locs,
+            turn: Direction::rotate_clockwise,
         }
     }
+    // What the guard does on meeting an obstacle, e.g.
+    // `Direction::rotate_counterclockwise` or `Direction::opposite`
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_turning(mut self, turn: fn(&Direction) -> Direction) -> Self {
+        self.turn = turn;
+        self
+    }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     fn len(&self) -> usize {
-        self.tiles.len()
+        self.grid.terrain().len()
     }
+    fn guard(&self) -> &Entity<Guard> {
+        &self.grid.entities()[0]
+    }
+    pub fn guard_loc(&self) -> Loc {
+        self.guard().loc()
+    }
+    pub fn guard_direction(&self) -> Direction {
+        self.guard().heading().unwrap()
+    }
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn count_visited(&self) -> usize {
         self.visited_locs.len()
     }
 
     pub fn advance_guard(&mut self) -> Option<Loc> {
-        self.tiles
-            .get_tile(self.guard_loc)
-            .unwrap()
-            .get_type_owned()
-            .mark_visited();
-        let new_loc = self.guard_loc.get_nearby(self.guard_direction, 1)?;
-        let next_loc_tile = self.tiles.get_tile(new_loc)?;
-        match next_loc_tile.get_type().tile_type {
-            TileType::Empty | TileType::Visited | TileType::Guard(_) => {
-                self.guard_loc = new_loc;
-                match self.visited_locs.contains(&new_loc) {
-                    true => {}
-                    false => self.visited_locs.push(new_loc),
-                }
+        let new_loc = self.guard_loc().get_nearby(self.guard_direction(), 1)?;
+        let next_tile_type = self.grid.terrain().get_tile(new_loc)?.get_type().tile_type;
+        let turned = (self.turn)(&self.guard_direction());
+        let guard = &mut self.grid.entities_mut()[0];
+        match next_tile_type {
+            TileType::Empty => {
+                guard.set_loc(new_loc);
+                self.visited_locs.insert(new_loc);
             }
             TileType::Obstacle => {
-                self.guard_direction = self.guard_direction.rotate_clockwise();
+                guard.set_heading(Some(turned));
+            }
+        }
+        Some(self.guard_loc())
+    }
+    pub fn patrol_guard(&mut self) -> PatrolReport {
+        self.patrol_guard_for(usize::MAX)
+    }
+    pub fn patrol_guard_for(&mut self, max_steps: usize) -> PatrolReport {
+        let mut seen = DirectedLocSet::new(self.grid.terrain().bounds().unwrap());
+        let mut report = PatrolReport {
+            reason: ExitReason::StepLimit,
+            steps: 0,
+            turns: 0,
+            path: vec![],
+        };
+        while report.steps < max_steps {
+            let state = (self.guard_loc(), self.guard_direction());
+            if !seen.insert(state.0, state.1) {
+                report.reason = ExitReason::Looped;
+                return report;
+            }
+            report.path.push(state);
+            match self.advance_guard() {
+                None => {
+                    report.reason = ExitReason::LeftMap;
+                    return report;
+                }
+                Some(loc) if loc == state.0 => report.turns += 1,
+                Some(_) => report.steps += 1,
             }
         }
-        Some(self.guard_loc)
+        report.path.push((self.guard_loc(), self.guard_direction()));
+        report
     }
-    pub fn patrol_guard(&mut self) {
-        loop {
-            match self.advance_guard().is_some() {
-                true => continue,
-                false => break,
+
+    // Walks the guard from its current spot with one extra obstacle in place,
+    // without touching the maze. Being back on a tile facing the same way
+    // means the patrol repeats forever.
+    fn loops_with_obstruction(&self, obstruction: Loc) -> bool {
+        let terrain = self.grid.terrain();
+        let mut seen = DirectedLocSet::new(terrain.bounds().unwrap());
+        let mut loc = self.guard_loc();
+        let mut direction = self.guard_direction();
+        while seen.insert(loc, direction) {
+            let Some(next) = loc.get_nearby(direction, 1) else {
+                return false;
+            };
+            let Some(tile) = terrain.get_tile(next) else {
+                return false;
+            };
+            if next == obstruction || tile.get_type().tile_type == TileType::Obstacle {
+                direction = (self.turn)(&direction);
+            } else {
+                loc = next;
             }
         }
+        true
+    }
+
+    // Only tiles on the guard's normal route can change where it goes, so
+    // those are the only places worth trying an obstacle.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn find_loop_obstructions(&self) -> Vec<Loc> {
+        let mut patrolled = self.clone();
+        patrolled.patrol_guard();
+        let start = self.guard_loc();
+        let mut obstructions: Vec<Loc> = patrolled
+            .visited_locs
+            .iter()
+            .filter(|loc| *loc != start)
+            .collect::<Vec<Loc>>()
+            .into_par_iter()
+            .filter(|loc| self.loops_with_obstruction(*loc))
+            .collect();
+        obstructions.sort();
+        obstructions
+    }
+}
+
+impl FromStr for Maze {
+    type Err = MazeError;
+
+    // Checks the whole grid up front so a bad input gets a line and column
+    // rather than a parser panic.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = s.trim_end_matches('\n').lines().collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err(MazeError::Empty);
+        }
+        let mut guard_seen = false;
+        for (line, row) in rows.iter().enumerate().map(|(i, row)| (i + 1, row)) {
+            let found = row.chars().count();
+            if found != width {
+                return Err(MazeError::UnevenRow {
+                    line,
+                    expected: width,
+                    found,
+                });
+            }
+            for (column, c) in row.chars().enumerate().map(|(i, c)| (i + 1, c)) {
+                if Guard::from_marker(c).is_some() {
+                    if guard_seen {
+                        return Err(MazeError::ExtraGuard { line, column });
+                    }
+                    guard_seen = true;
+                } else if !Tile::valid_chars().contains(&c) {
+                    return Err(MazeError::BadTile {
+                        line,
+                        column,
+                        found: c,
+                    });
+                }
+            }
+        }
+        if !guard_seen {
+            return Err(MazeError::NoGuard);
+        }
+        let grid = LayeredGrid::from_puzzle_input(&format!("{}\n", rows.join("\n")));
+        Ok(Self::from_grid(grid))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use test_case::test_case;
 
     #[test]
     fn test_parse_input() {
@@ -133,24 +366,72 @@ mod tests {
         assert_eq!(actual.len(), 100);
     }
 
+    #[test]
+    fn test_maze_honours_input() {
+        let mut maze = Maze::new(".#.\n...\n.^.");
+        assert_eq!(maze.len(), 9);
+        assert_eq!(maze.guard_loc(), Loc::new(1, 2));
+        let report = maze.patrol_guard();
+        assert_eq!(report.reason(), ExitReason::LeftMap);
+        assert_eq!(maze.count_visited(), 3);
+    }
+
+    #[test]
+    fn test_from_terrain() {
+        let terrain = Collection::from_puzzle_input("..\n#.\n");
+        let maze = Maze::from_terrain(terrain.clone(), Loc::new(1, 1), Direction::West).unwrap();
+        assert_eq!(maze.grid.to_string(), "..\n#<\n");
+        assert_eq!(
+            Maze::from_terrain(terrain.clone(), Loc::new(0, 1), Direction::West).err(),
+            Some(MazeError::GuardNotOnFloor(Loc::new(0, 1)))
+        );
+        assert_eq!(
+            Maze::from_terrain(terrain, Loc::new(2, 0), Direction::West).err(),
+            Some(MazeError::GuardNotOnFloor(Loc::new(2, 0)))
+        );
+    }
+
+    #[test_case("", MazeError::Empty)]
+    #[test_case("..\n.", MazeError::UnevenRow { line: 2, expected: 2, found: 1 })]
+    #[test_case("..\n.?\n", MazeError::BadTile { line: 2, column: 2, found: '?' })]
+    #[test_case("..\n..\n", MazeError::NoGuard)]
+    #[test_case(".^\n>.\n", MazeError::ExtraGuard { line: 2, column: 1 })]
+    fn test_from_str_errors(inp: &str, exp: MazeError) {
+        assert_eq!(inp.parse::<Maze>().err(), Some(exp));
+    }
+
+    #[test]
+    fn test_maze_error_display() {
+        let err = "..\n.?\n".parse::<Maze>().err().unwrap();
+        assert_eq!(err.to_string(), "line 2, column 2: unexpected '?'");
+    }
+
+    #[test]
+    fn test_guard_is_not_terrain() {
+        let maze = Maze::new(PUZZLE_INPUT);
+        let guard_tile = maze.grid.terrain().get_tile(maze.guard_loc()).unwrap();
+        assert_eq!(guard_tile.get_type().tile_type, TileType::Empty);
+        assert_eq!(maze.grid.to_string(), PUZZLE_INPUT);
+    }
+
     #[test]
     fn test_advance_guard() {
         let mut maze = Maze::new(PUZZLE_INPUT);
-        assert_eq!(maze.guard_loc, Loc::new(4, 6));
+        assert_eq!(maze.guard_loc(), Loc::new(4, 6));
         maze.advance_guard();
-        assert_eq!(maze.guard_loc, Loc::new(4, 5));
+        assert_eq!(maze.guard_loc(), Loc::new(4, 5));
         maze.advance_guard();
         maze.advance_guard();
         maze.advance_guard();
         maze.advance_guard();
-        assert_eq!(maze.guard_direction, Direction::North);
-        assert_eq!(maze.guard_loc, Loc::new(4, 1));
+        assert_eq!(maze.guard_direction(), Direction::North);
+        assert_eq!(maze.guard_loc(), Loc::new(4, 1));
         maze.advance_guard();
-        assert_eq!(maze.guard_direction, Direction::East);
-        assert_eq!(maze.guard_loc, Loc::new(4, 1));
+        assert_eq!(maze.guard_direction(), Direction::East);
+        assert_eq!(maze.guard_loc(), Loc::new(4, 1));
         maze.advance_guard();
-        assert_eq!(maze.guard_direction, Direction::East);
-        assert_eq!(maze.guard_loc, Loc::new(5, 1));
+        assert_eq!(maze.guard_direction(), Direction::East);
+        assert_eq!(maze.guard_loc(), Loc::new(5, 1));
     }
 
     #[test]
@@ -160,4 +441,72 @@ mod tests {
         let actual = maze.count_visited();
         assert_eq!(actual, 41);
     }
+
+    #[test]
+    fn test_patrol_report() {
+        let mut maze = Maze::new(PUZZLE_INPUT);
+        let report = maze.patrol_guard();
+        assert_eq!(report.reason(), ExitReason::LeftMap);
+        assert_eq!(report.turns(), 10);
+        assert_eq!(report.path().len(), report.steps() + report.turns() + 1);
+        assert_eq!(report.path()[0], (Loc::new(4, 6), Direction::North));
+        assert_eq!(
+            report.path().last(),
+            Some(&(Loc::new(7, 9), Direction::South))
+        );
+    }
+
+    #[test]
+    fn test_patrol_step_limit() {
+        let mut maze = Maze::new(PUZZLE_INPUT);
+        let report = maze.patrol_guard_for(5);
+        assert_eq!(report.reason(), ExitReason::StepLimit);
+        assert_eq!(report.steps(), 5);
+        assert_eq!(report.turns(), 0);
+        assert_eq!(maze.guard_loc(), Loc::new(4, 1));
+    }
+
+    #[test]
+    fn test_patrol_loops() {
+        let mut maze = Maze::new(PUZZLE_INPUT);
+        assert!(maze.loops_with_obstruction(Loc::new(3, 6)));
+        maze.grid
+            .terrain_mut()
+            .get_tile_mut(Loc::new(3, 6))
+            .unwrap()
+            .set_type(Tile::new(TileType::Obstacle));
+        let report = maze.patrol_guard();
+        assert_eq!(report.reason(), ExitReason::Looped);
+    }
+
+    #[test_case(Direction::rotate_clockwise, Direction::East)]
+    #[test_case(Direction::rotate_counterclockwise, Direction::West)]
+    #[test_case(Direction::opposite, Direction::South)]
+    #[test_case(Direction::rotate_clockwise_45, Direction::NorthEast)]
+    fn test_turning_policy(turn: fn(&Direction) -> Direction, exp: Direction) {
+        let mut maze = Maze::new(PUZZLE_INPUT).with_turning(turn);
+        let report = maze.patrol_guard();
+        assert_eq!(report.path()[5], (Loc::new(4, 1), Direction::North));
+        assert_eq!(report.path()[6], (Loc::new(4, 1), exp));
+    }
+
+    #[test]
+    fn test_diagonal_guard_marker() {
+        let mut maze = Maze::new(PUZZLE_INPUT).with_turning(Direction::rotate_clockwise_45);
+        maze.patrol_guard_for(6);
+        assert_eq!(maze.guard_direction(), Direction::NorthEast);
+        let drawn = maze.grid.to_string();
+        assert!(drawn.contains('↗'));
+        let redrawn: Maze = drawn.parse().unwrap();
+        assert_eq!(redrawn.guard_direction(), Direction::NorthEast);
+    }
+
+    #[test]
+    fn test_find_loop_obstructions() {
+        let maze = Maze::new(PUZZLE_INPUT);
+        let actual = maze.find_loop_obstructions();
+        assert_eq!(actual.len(), 6);
+        assert!(actual.contains(&Loc::new(3, 6)));
+        assert_eq!(maze.count_visited(), 1);
+    }
 }
@@ -1,5 +1,8 @@
 mod shared;
+use shared::{CalibrationList, Operator, PUZZLE_INPUT};
 
 fn main() {
-
+    let calibration_list = CalibrationList::from(PUZZLE_INPUT);
+    let actual = calibration_list.get_total_calibration_result_with(Operator::all());
+    println!("{}", actual);
 }
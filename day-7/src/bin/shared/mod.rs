@@ -11,11 +11,27 @@ pub const PUZZLE_INPUT: &str = include_str!("../../data/puzzle_input.txt");
 pub enum Operator {
     Add,
     Mult,
+    Concat,
 }
 impl Operator {
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn all() -> Vec<Operator> {
+        vec![Operator::Add, Operator::Mult, Operator::Concat]
+    }
+    pub fn basic() -> Vec<Operator> {
         vec![Operator::Add, Operator::Mult]
     }
+    // None when the result doesn't fit, which no test value can match
+    fn apply(&self, left: usize, right: usize) -> Option<usize> {
+        match self {
+            Operator::Add => left.checked_add(right),
+            Operator::Mult => left.checked_mul(right),
+            Operator::Concat => 10usize
+                .checked_pow(right.checked_ilog10().unwrap_or(0) + 1)
+                .and_then(|shift| left.checked_mul(shift))
+                .and_then(|shifted| shifted.checked_add(right)),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -38,20 +54,18 @@ impl Equation {
         self.test_value
     }
     fn could_be_valid(&self, ops: Vec<Operator>) -> bool {
-        let possible_combos = get_possible_combinations(self.inputs.len() - 1, Operator::all());
+        let possible_combos = get_possible_combinations(self.inputs.len() - 1, ops);
         for combo in possible_combos.iter() {
-            let mut calibration_result = self.inputs[0];
+            let mut calibration_result = Some(self.inputs[0]);
             for (i, operator) in combo.iter().enumerate() {
-                match operator {
-                    Operator::Add => calibration_result += self.inputs[i + 1],
-                    Operator::Mult => calibration_result *= self.inputs[i + 1],
-                };
+                calibration_result = calibration_result
+                    .and_then(|result| operator.apply(result, self.inputs[i + 1]));
             }
-            if calibration_result == self.test_value {
+            if calibration_result == Some(self.test_value) {
                 return true;
             }
         }
-        return false;
+        false
     }
 }
 
@@ -191,6 +205,7 @@ pub struct CalibrationList {
 }
 
 impl CalibrationList {
+    #[cfg_attr(not(test), allow(dead_code))]
     fn len(&self) -> usize {
         self.equations.len()
     }
@@ -201,8 +216,13 @@ impl CalibrationList {
             .collect()
     }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn get_total_calibration_result(&self) -> usize {
-        self.get_valid_equations(Operator::all())
+        self.get_total_calibration_result_with(Operator::basic())
+    }
+
+    pub fn get_total_calibration_result_with(&self, ops: Vec<Operator>) -> usize {
+        self.get_valid_equations(ops)
             .iter()
             .map(|e| e.get_test_value())
             .sum()
@@ -211,11 +231,7 @@ impl CalibrationList {
 
 impl From<&str> for CalibrationList {
     fn from(value: &str) -> Self {
-        let equations: Vec<Equation> = PUZZLE_INPUT
-            .lines()
-            .into_iter()
-            .map(|l| Equation::from(l))
-            .collect();
+        let equations: Vec<Equation> = value.lines().map(Equation::from).collect();
         Self { equations }
     }
 }
@@ -288,8 +304,59 @@ mod tests {
     #[test_case("21037: 9 7 18 13", false)]
     #[test_case("292: 11 6 16 20", true)]
     fn test_could_be_valid(inp: &str, expected: bool) {
+        let equation = Equation::from(inp);
+        let actual = equation.could_be_valid(Operator::basic());
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("190: 10 19", true)]
+    #[test_case("3267: 81 40 27", true)]
+    #[test_case("83: 17 5", false)]
+    #[test_case("156: 15 6", true)]
+    #[test_case("7290: 6 8 6 15", true)]
+    #[test_case("161011: 16 10 13", false)]
+    #[test_case("192: 17 8 14", true)]
+    #[test_case("21037: 9 7 18 13", false)]
+    #[test_case("292: 11 6 16 20", true)]
+    fn test_could_be_valid_with_concat(inp: &str, expected: bool) {
         let equation = Equation::from(inp);
         let actual = equation.could_be_valid(Operator::all());
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_total_calibration_result_with_concat() {
+        let calibration_list = CalibrationList::from(PUZZLE_INPUT);
+        let actual = calibration_list.get_total_calibration_result_with(Operator::all());
+        assert_eq!(actual, 11387);
+    }
+
+    #[test_case("29: 10 19", vec![Operator::Add], true)]
+    #[test_case("190: 10 19", vec![Operator::Add], false)]
+    #[test_case("1019: 10 19", vec![Operator::Add, Operator::Mult], false)]
+    #[test_case("1019: 10 19", vec![Operator::Concat], true)]
+    #[test_case("5: 18446744073709551615 9", Operator::all(), false)]
+    fn test_could_be_valid_honours_ops(inp: &str, ops: Vec<Operator>, expected: bool) {
+        let equation = Equation::from(inp);
+        assert_eq!(equation.could_be_valid(ops), expected);
+    }
+
+    #[test_case(Operator::Concat, 15, 6, Some(156))]
+    #[test_case(Operator::Concat, 12, 345, Some(12345))]
+    #[test_case(Operator::Concat, 7, 0, Some(70))]
+    #[test_case(Operator::Concat, 10, 10, Some(1010))]
+    #[test_case(Operator::Concat, usize::MAX, 1, None)]
+    #[test_case(Operator::Concat, 1, usize::MAX, None)]
+    #[test_case(Operator::Add, usize::MAX, 1, None)]
+    #[test_case(Operator::Mult, usize::MAX, 2, None)]
+    fn test_apply(op: Operator, left: usize, right: usize, expected: Option<usize>) {
+        assert_eq!(op.apply(left, right), expected);
+    }
+
+    #[test]
+    fn test_parse_input_honours_value() {
+        let actual = CalibrationList::from("190: 10 19\n83: 17 5\n");
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual.get_total_calibration_result(), 190);
+    }
 }